@@ -0,0 +1,135 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compares the cost of closing due auctions in `before_commit` using the expiry index
+//! with the full scan of open auctions.
+//!
+//! Run with `cargo bench --bench before_commit`.
+
+use std::time::{Duration as StdDuration, Instant};
+
+use chrono::Duration;
+use exonum::blockchain::Service;
+use exonum::crypto::{self, Hash, PublicKey};
+use exonum::helpers::Height;
+use exonum::storage::Fork;
+use exonum_testkit::{TestKit, TestKitBuilder};
+use exonum_time::{time_provider::MockTimeProvider, TimeService};
+
+use exonum_cryptoowls::data_layout::{
+    Auction, AuctionExpiry, AuctionKind, AuctionPhase, AuctionState, CloseReason,
+};
+use exonum_cryptoowls::schema::CryptoOwlsSchema;
+use exonum_cryptoowls::service::CryptoOwlsService;
+use exonum_cryptoowls::transactions::current_time;
+
+const ITERATIONS: u32 = 100;
+
+/// Creates a blockchain with `count` open auctions which are not due yet.
+fn init_testkit(count: u64) -> TestKit {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CryptoOwlsService::default())
+        .with_service(TimeService::with_provider(MockTimeProvider::default()))
+        .create();
+    testkit.create_blocks_until(Height(2));
+
+    let mut fork = testkit.blockchain_mut().fork();
+    {
+        let ts = current_time(&fork).unwrap();
+        let seller = PublicKey::zero();
+        let mut schema = CryptoOwlsSchema::new(&mut fork);
+        for auction_id in 0..count {
+            let owl_id = crypto::hash(&auction_id.to_be_bytes());
+            let ends_at = ts + Duration::days(1);
+            let auction = Auction {
+                public_key: seller,
+                owl_id,
+                start_price: 10,
+                duration: 86_400,
+                cancellable_with_bids: false,
+                reserve_price: 0,
+                buyout_price: 0,
+                kind: AuctionKind::English,
+                reveal_duration: 0,
+                floor_price: 0,
+                decay_interval: 0,
+                reserve_commitment: Hash::zero(),
+            };
+            schema.auctions_mut().push(AuctionState {
+                id: auction_id,
+                auction,
+                started_at: ts,
+                bidding_merkle_root: Hash::zero(),
+                closed: false,
+                close_reason: CloseReason::Open,
+                ends_at,
+                phase: AuctionPhase::Bidding,
+                commitments_merkle_root: Hash::zero(),
+            });
+            schema.owl_auction_mut().put(&owl_id, auction_id);
+            schema.auction_expiry_mut().insert(AuctionExpiry {
+                ends_at,
+                auction_id,
+            });
+        }
+    }
+    testkit
+        .blockchain_mut()
+        .merge(fork.into_patch())
+        .expect("Unable to add auctions");
+    testkit
+}
+
+/// The previous `before_commit` implementation: visits every open auction.
+fn scan_open_auctions(fork: &mut Fork) {
+    let ts = current_time(&*fork).unwrap();
+    let schema = CryptoOwlsSchema::new(fork);
+    let due_auctions = schema
+        .owl_auction()
+        .into_iter()
+        .map(|(_, auction_id)| schema.auctions().get(auction_id).unwrap())
+        .filter(|auction_state| auction_state.ends_at <= ts)
+        .count();
+    assert_eq!(due_auctions, 0);
+}
+
+fn measure<F: FnMut(&mut Fork)>(testkit: &mut TestKit, mut f: F) -> StdDuration {
+    let mut total = StdDuration::default();
+    for _ in 0..ITERATIONS {
+        let mut fork = testkit.blockchain_mut().fork();
+        let start = Instant::now();
+        f(&mut fork);
+        total += start.elapsed();
+    }
+    total / ITERATIONS
+}
+
+fn main() {
+    println!(
+        "{:>15} {:>20} {:>20}",
+        "open auctions", "scan, us/block", "expiry index, us/block"
+    );
+    for &count in &[100, 1_000, 5_000, 10_000] {
+        let mut testkit = init_testkit(count);
+        let scan = measure(&mut testkit, scan_open_auctions);
+        let service = CryptoOwlsService::default();
+        let index = measure(&mut testkit, |fork| service.before_commit(fork));
+        println!(
+            "{:>15} {:>20} {:>20}",
+            count,
+            scan.as_micros(),
+            index.as_micros()
+        );
+    }
+}
@@ -0,0 +1,96 @@
+// Generates the owl image template of the server-side renderer (`src/owl.svg`)
+// and the golden SVG images for its tests (`tests/render.rs`).
+// Every image is `OwlIcon.vue` mounted in jsdom and drawn by `OwlIcon.draw`. The template
+// is the same image with placeholders for the color of the feathers (`{{color}}`) and
+// for the display of each body part variant (`{{eyes_0}}` and so on).
+// Run `npm run proto` before generating the images.
+var fs = require('fs');
+var path = require('path');
+var JSDOM = require('jsdom').JSDOM;
+
+var dom = new JSDOM('<!DOCTYPE html><html><body></body></html>');
+global.window = dom.window;
+global.document = dom.window.document;
+// svg.js checks the SVG support with `createSVGRect`, which jsdom does not implement
+window.SVGSVGElement.prototype.createSVGRect = function() {
+  return {x: 0, y: 0, width: 0, height: 0};
+};
+
+var Vue = require('vue');
+var compiler = require('vue-template-compiler');
+var svg = require('svg.js');
+var splitDNA = require('./split-dna');
+
+var TEMPLATE = path.join(__dirname, '../../src/owl.svg');
+var FIXTURES = path.join(__dirname, '../../tests/fixtures/owls');
+var DNAS = [0x00000000, 0xffffffff, 0x1b332211, 0xe4c0ffee, 0x8000ff00, 0x36a0522d];
+var XMLNS = 'http://www.w3.org/2000/xmlns/';
+var BODY_PARTS = ['eyes', 'wings', 'chest', 'tail'];
+var VARIANTS = 4;
+
+// Same as `plugins/svg.js` and `plugins/blockchain.js`
+Vue.config.productionTip = false;
+Vue.prototype.$svg = svg;
+Vue.prototype.$blockchain = {splitDNA: splitDNA};
+
+var component = compiler.parseComponent(
+  fs.readFileSync(path.join(__dirname, '../src/components/OwlIcon.vue'), 'utf8')
+);
+var script = {};
+new Function('module', component.script.content)(script);
+var OwlIcon = script.exports;
+var compiled = compiler.compileToFunctions(component.template.content);
+OwlIcon.render = compiled.render;
+OwlIcon.staticRenderFns = compiled.staticRenderFns;
+
+function draw(dna) {
+  var vm = new Vue({
+    render: function(h) {
+      return h(OwlIcon, {props: {dna: dna}});
+    }
+  }).$mount();
+
+  // `OwlIcon` draws the owl on the next tick after it is mounted
+  return Vue.nextTick().then(function() {
+    var image = vm.$el;
+    // Remove the definitions and the namespaces svg.js adds for its own use
+    var defs = image.querySelector('defs');
+    if (defs) {
+      image.removeChild(defs);
+    }
+    Array.prototype.slice.call(image.attributes).forEach(function(attribute) {
+      if (attribute.namespaceURI === XMLNS) {
+        image.removeAttributeNode(attribute);
+      }
+    });
+    vm.$destroy();
+    return image;
+  });
+}
+
+function template(image) {
+  Array.prototype.slice.call(image.querySelectorAll('.st3')).forEach(function(element) {
+    element.setAttribute('fill', '{{color}}');
+  });
+  BODY_PARTS.forEach(function(part) {
+    for (var variant = 0; variant < VARIANTS; variant++) {
+      var id = part + '_' + variant;
+      image.querySelector('#' + id).setAttribute('display', '{{' + id + '}}');
+    }
+  });
+  return image;
+}
+
+fs.mkdirSync(FIXTURES, {recursive: true});
+DNAS.reduce(function(done, dna) {
+  return done.then(function() {
+    return draw(dna);
+  }).then(function(image) {
+    var name = ('00000000' + dna.toString(16)).slice(-8) + '.svg';
+    fs.writeFileSync(path.join(FIXTURES, name), image.outerHTML);
+  });
+}, Promise.resolve()).then(function() {
+  return draw(0);
+}).then(function(image) {
+  fs.writeFileSync(TEMPLATE, template(image).outerHTML);
+});
@@ -0,0 +1,16 @@
+// Generates the test vectors for DNA decoding in the service (`tests/genetics.rs`).
+var fs = require('fs');
+var path = require('path');
+var splitDNA = require('./split-dna');
+
+var VECTORS = path.join(__dirname, '../../tests/fixtures/split_dna.json');
+var DNAS = [
+  0x00000000, 0xffffffff, 0x1b332211, 0xe4c0ffee, 0x8000ff00, 0x36a0522d,
+  0x00e40000, 0x001b0000, 0x00805a3c, 0x7f3f0102
+];
+
+var vectors = DNAS.map(function(dna) {
+  return {dna: dna, split: splitDNA(dna)};
+});
+
+fs.writeFileSync(VECTORS, JSON.stringify(vectors, null, 2) + '\n');
@@ -0,0 +1,17 @@
+// Loads `splitDNA` from `plugins/blockchain.js` as is, so that the fixtures generated by
+// the scripts follow the frontend code rather than a copy of it.
+// Run `npm run proto` before using it.
+var fs = require('fs');
+var path = require('path');
+var Exonum = require('exonum-client');
+var proto = require('../proto/proto.js');
+
+var DNA = Exonum.newType(proto.exonum.DNA);
+
+var source = fs.readFileSync(path.join(__dirname, '../src/plugins/blockchain.js'), 'utf8');
+var match = source.match(/splitDNA: (dna => \{[\s\S]*?\n {6}\}),/);
+if (!match) {
+  throw new Error('No splitDNA in plugins/blockchain.js');
+}
+
+module.exports = new Function('Exonum', 'DNA', 'return ' + match[1])(Exonum, DNA);
@@ -20,31 +20,172 @@ pub const CRYPTOOWLS_SERVICE_ID: u16 = 521;
 /// Unique service name which will be used in API and configuration.
 pub const CRYPTOOWLS_SERVICE_NAME: &str = "cryptoowls";
 
-/// Sum to be issued each time.
+/// Default sum to be issued each time (see `config::CryptoOwlsConfig`).
 pub const ISSUE_AMOUNT: u64 = 100;
 
-/// Timeout (seconds) before user will be able to issue funds again.
+/// Default timeout (seconds) before user will be able to issue funds again.
 pub const ISSUE_TIMEOUT: i64 = 60;
 
-/// Timeout (seconds) before user will be able to breed owl again.
+/// Default timeout (seconds) before user will be able to breed owl again.
 pub const BREEDING_TIMEOUT: i64 = 60;
 
-/// Breeding price.
+/// Default breeding price.
 pub const BREEDING_PRICE: u64 = 42;
 
+/// Default timeout (seconds) before the egg is ready to hatch.
+pub const HATCHING_TIMEOUT: i64 = 60;
+
+/// Probability (per mille) that the child gene differs from the genes of both parents
+/// when they are equal.
+pub const MUTATION_RATE: u32 = 200;
+
+/// Number of generations of ancestors taken into account by the inbreeding coefficient.
+pub const INBREEDING_DEPTH: u64 = 4;
+
+/// Version of the service data layout. Data stored by earlier versions is migrated
+/// before the first transaction after the upgrade (see `schema::CryptoOwlsSchema::migrate`).
+pub const SCHEMA_VERSION: u32 = 4;
+
+/// Default time window (seconds) before the auction end. A bid made within this window
+/// extends the auction so that other participants have time to respond.
+pub const AUCTION_EXTENSION_WINDOW: i64 = 60;
+
+/// Service configuration.
+pub mod config {
+    use failure::{bail, Error};
+    use serde_derive::{Deserialize, Serialize};
+
+    use crate::{
+        AUCTION_EXTENSION_WINDOW, BREEDING_PRICE, BREEDING_TIMEOUT, HATCHING_TIMEOUT, ISSUE_AMOUNT,
+        ISSUE_TIMEOUT,
+    };
+
+    /// Gameplay parameters stored in the service section of the consensus configuration.
+    /// They are changed through the `exonum-configuration` service voting.
+    /// Missing parameters take their default values, so that configurations written
+    /// before a parameter was introduced stay valid.
+    ///
+    /// The configuration service does not check the service sections of the proposals.
+    /// If the voted section can not be parsed or fails `validate`, the service logs
+    /// an error and keeps the parameters of the latest valid configuration.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct CryptoOwlsConfig {
+        /// Sum to be issued each time.
+        pub issue_amount: u64,
+        /// Timeout (seconds) before user will be able to issue funds again.
+        pub issue_timeout: i64,
+        /// Timeout (seconds) before user will be able to breed owl again.
+        pub breeding_timeout: i64,
+        /// Breeding price.
+        pub breeding_price: u64,
+        /// Timeout (seconds) before the egg is ready to hatch.
+        pub hatching_timeout: i64,
+        /// Reject breeding of parents with their children and of siblings.
+        /// Disabled by default, the inbreeding only raises the mutation rate.
+        pub forbid_inbreeding: bool,
+        /// Additional mutation rate (per mille) of a completely inbred owl. The actual
+        /// addition is proportional to the inbreeding coefficient. Zero disables it.
+        pub inbreeding_mutation_rate: u32,
+        /// Time window (seconds) before the auction end. A bid made within this window
+        /// extends the auction so that other participants have time to respond.
+        pub auction_extension_window: i64,
+    }
+
+    impl Default for CryptoOwlsConfig {
+        fn default() -> Self {
+            CryptoOwlsConfig {
+                issue_amount: ISSUE_AMOUNT,
+                issue_timeout: ISSUE_TIMEOUT,
+                breeding_timeout: BREEDING_TIMEOUT,
+                breeding_price: BREEDING_PRICE,
+                hatching_timeout: HATCHING_TIMEOUT,
+                forbid_inbreeding: false,
+                inbreeding_mutation_rate: 0,
+                auction_extension_window: AUCTION_EXTENSION_WINDOW,
+            }
+        }
+    }
+
+    /// Upper bound of the timeouts (seconds), so that the time arithmetic never overflows.
+    pub const MAX_TIMEOUT: i64 = 100 * 365 * 24 * 60 * 60;
+
+    impl CryptoOwlsConfig {
+        /// Checks if the parameters are within the bounds the service can work with.
+        pub fn validate(&self) -> Result<(), Error> {
+            let timeouts = [
+                ("issue_timeout", self.issue_timeout),
+                ("breeding_timeout", self.breeding_timeout),
+                ("hatching_timeout", self.hatching_timeout),
+                ("auction_extension_window", self.auction_extension_window),
+            ];
+            for (name, timeout) in &timeouts {
+                if *timeout < 0 || *timeout > MAX_TIMEOUT {
+                    bail!("{} should be between 0 and {} seconds", name, MAX_TIMEOUT);
+                }
+            }
+            if self.inbreeding_mutation_rate > 1000 {
+                bail!("inbreeding_mutation_rate should not exceed 1000 per mille");
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Sources of randomness for owl breeding.
+pub mod randomness {
+    use std::fmt::Debug;
+
+    use exonum::blockchain::Schema;
+    use exonum::crypto::Hash;
+    use exonum::helpers::Height;
+    use exonum::storage::Snapshot;
+
+    /// Source of randomness used to hatch eggs. The randomness for the egg should be
+    /// unknown to the breeder (and to the owner) until the egg is ready to hatch.
+    pub trait RandomnessSource: Debug + Send + Sync {
+        /// Returns the seed for eggs which became ready to hatch in the block
+        /// at the given height, or `None` if it is not available yet.
+        fn seed(&self, snapshot: &dyn Snapshot, hatching_height: Height) -> Option<Hash>;
+    }
+
+    /// Uses the hash of the block in which the egg became ready to hatch. The hash
+    /// is unknown until the block is committed, so the egg is hatched in the next block.
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct BlockHashRandomness;
+
+    impl RandomnessSource for BlockHashRandomness {
+        fn seed(&self, snapshot: &dyn Snapshot, hatching_height: Height) -> Option<Hash> {
+            Schema::new(snapshot)
+                .block_hashes_by_height()
+                .get(hatching_height.0)
+        }
+    }
+}
+
 /// Data structures stored in blockchain.
 pub mod data_layout {
+    use std::cmp;
+
     use chrono::{DateTime, Utc};
+    use enum_primitive_derive::Primitive;
+    use failure::format_err;
+    use num_traits::{FromPrimitive, ToPrimitive};
     use serde_derive::{Deserialize, Serialize};
 
-    use exonum::crypto::{Hash, PublicKey};
+    use exonum::crypto::{Hash, PublicKey, HASH_SIZE};
+    use exonum::proto::ProtobufConvert;
+    use exonum::storage::StorageKey;
     use exonum_derive::ProtobufConvert;
 
+    use crate::genetics::{Traits, BODY_PART_VARIANTS};
+
     /// CryptoOwl. Unique identifier of the owl is a hash of this data structure.
+    /// The owl keeps its identifier when it is renamed.
     #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
     #[exonum(pb = "crate::proto::CryptoOwl")]
     pub struct CryptoOwl {
-        /// Name (should be unique).
+        /// Name given at birth.
         pub name: String,
         /// DNA.
         pub dna: u32,
@@ -60,6 +201,113 @@ pub mod data_layout {
         pub owner: PublicKey,
         /// Time of the last breeding.
         pub last_breeding: DateTime<Utc>,
+        /// Current name (unique regardless of case and whitespace,
+        /// see `CryptoOwlsSchema::owl_names`).
+        pub name: String,
+    }
+
+    /// Owl egg. DNA of the owl is unknown until the egg is hatched.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::Egg")]
+    pub struct Egg {
+        /// Name of the future owl.
+        pub name: String,
+        /// Father DNA.
+        pub father_dna: u32,
+        /// Mother DNA.
+        pub mother_dna: u32,
+        /// Owner.
+        pub owner: PublicKey,
+        /// Height of the block with the breeding transaction.
+        pub laid_at: u64,
+        /// Time of the breeding.
+        pub bred_at: DateTime<Utc>,
+        /// Time when the egg is ready to hatch.
+        pub hatch_at: DateTime<Utc>,
+        /// Height of the block which hash hatches the egg, or zero if the egg is not ready yet.
+        pub hatching_height: u64,
+        /// Father identifier.
+        pub father_id: Hash,
+        /// Mother identifier.
+        pub mother_id: Hash,
+        /// Inbreeding coefficient (per mille).
+        pub inbreeding: u32,
+    }
+
+    /// Key of the index of eggs which are not ready to hatch. Keys are ordered by
+    /// the hatching time first, so the eggs due to be ready are at the beginning of the index.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct EggIncubation {
+        /// Time when the egg is ready to hatch.
+        pub hatch_at: DateTime<Utc>,
+        /// Egg identifier.
+        pub egg_id: Hash,
+    }
+
+    impl StorageKey for EggIncubation {
+        fn size(&self) -> usize {
+            self.hatch_at.size() + self.egg_id.size()
+        }
+
+        fn write(&self, buffer: &mut [u8]) {
+            let split = self.hatch_at.size();
+            self.hatch_at.write(&mut buffer[..split]);
+            self.egg_id.write(&mut buffer[split..]);
+        }
+
+        fn read(buffer: &[u8]) -> Self {
+            let split = buffer.len() - HASH_SIZE;
+            EggIncubation {
+                hatch_at: DateTime::<Utc>::read(&buffer[..split]),
+                egg_id: Hash::read(&buffer[split..]),
+            }
+        }
+    }
+
+    /// Key of the index of eggs which wait for the randomness to hatch. Keys are ordered
+    /// by the hatching height first, so the eggs marked earlier are at the beginning of the index.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct EggHatching {
+        /// Height of the block which hash hatches the egg.
+        pub hatching_height: u64,
+        /// Egg identifier.
+        pub egg_id: Hash,
+    }
+
+    impl StorageKey for EggHatching {
+        fn size(&self) -> usize {
+            self.hatching_height.size() + self.egg_id.size()
+        }
+
+        fn write(&self, buffer: &mut [u8]) {
+            let split = self.hatching_height.size();
+            self.hatching_height.write(&mut buffer[..split]);
+            self.egg_id.write(&mut buffer[split..]);
+        }
+
+        fn read(buffer: &[u8]) -> Self {
+            EggHatching {
+                hatching_height: u64::read(&buffer[..8]),
+                egg_id: Hash::read(&buffer[8..]),
+            }
+        }
+    }
+
+    /// Owl metadata which is not a part of the owl identifier.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::OwlMetadata")]
+    pub struct OwlMetadata {
+        /// Father identifier (zero hash for the owls of the starter pack).
+        pub father_id: Hash,
+        /// Mother identifier (zero hash for the owls of the starter pack).
+        pub mother_id: Hash,
+        /// Generation: zero for the starter pack, otherwise the highest
+        /// generation of the parents plus one.
+        pub generation: u64,
+        /// Time of the birth.
+        pub born_at: DateTime<Utc>,
+        /// Inbreeding coefficient (per mille), see `CryptoOwlsSchema::inbreeding_coefficient`.
+        pub inbreeding: u32,
     }
 
     /// User
@@ -102,8 +350,109 @@ pub mod data_layout {
         /// Type `Duration` is not used because
         /// the trait `ProtobufConvert` is not implemented for `chrono::Duration`.
         pub duration: u64,
+        /// If set, the seller may cancel the auction even if it has bids.
+        /// The highest bidder gets their reserved funds back in this case.
+        pub cancellable_with_bids: bool,
+        /// Minimal price the seller agrees to sell the owl for. Zero if not set
+        /// or not revealed yet (see `reserve_commitment`).
+        pub reserve_price: u64,
+        /// Price at which the auction is settled immediately. Zero if not set.
+        pub buyout_price: u64,
+        /// Auction kind.
+        pub kind: AuctionKind,
+        /// Sealed-bid auctions and auctions with a reserve price only. Bids (and the reserve
+        /// price) are revealed during the `reveal_duration` seconds after bidding is over.
+        pub reveal_duration: u64,
+        /// Dutch auctions only. Price reached at the end of the auction.
+        pub floor_price: u64,
+        /// Dutch auctions only. Price drops every `decay_interval` seconds.
+        /// Zero if the price drops linearly.
+        pub decay_interval: u64,
+        /// Hash of the reserve price, the secret salt and the seller key, or zero hash
+        /// if the reserve price is not set (see `reserve_price_commitment`).
+        /// The reserve price is unknown to bidders until the seller reveals it.
+        pub reserve_commitment: Hash,
+    }
+
+    impl Auction {
+        /// Returns `true` if something is revealed after bidding is over:
+        /// sealed bids or the reserve price.
+        pub fn has_reveal_phase(&self) -> bool {
+            self.kind.is_sealed() || self.reserve_commitment != Hash::zero()
+        }
+    }
+
+    /// Reason why the auction was closed.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Primitive)]
+    pub enum CloseReason {
+        /// Auction is not closed yet.
+        Open = 0,
+        /// Auction time is over and nobody made a bid.
+        Expired = 1,
+        /// Auction was cancelled by the seller.
+        Cancelled = 2,
+        /// Owl was sold to the highest bidder.
+        Sold = 3,
+        /// Auction time is over and the highest bid is below the revealed reserve price.
+        ReserveNotMet = 4,
+    }
+
+    /// Kind of the auction.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Primitive)]
+    pub enum AuctionKind {
+        /// Open ascending-price auction. The last bid wins.
+        English = 0,
+        /// Sealed-bid auction. The highest bidder pays their own bid.
+        SealedFirstPrice = 1,
+        /// Sealed-bid (Vickrey) auction. The highest bidder pays the second highest bid.
+        SealedSecondPrice = 2,
+        /// Descending-price auction. The first bid covering the current price wins.
+        Dutch = 3,
     }
 
+    impl AuctionKind {
+        /// Returns `true` if bids are committed and revealed later.
+        pub fn is_sealed(self) -> bool {
+            match self {
+                AuctionKind::SealedFirstPrice | AuctionKind::SealedSecondPrice => true,
+                _ => false,
+            }
+        }
+    }
+
+    /// Phase of the auction.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Primitive)]
+    pub enum AuctionPhase {
+        /// Bids (or commitments to sealed bids) are accepted.
+        Bidding = 0,
+        /// Sealed bids are revealed.
+        Reveal = 1,
+        /// Auction is closed.
+        Closed = 2,
+    }
+
+    /// Implements `ProtobufConvert` for enums stored as `uint32` in protobuf messages.
+    macro_rules! impl_protobuf_convert_for_enum {
+        ($name:ident) => {
+            impl ProtobufConvert for $name {
+                type ProtoStruct = u32;
+
+                fn to_pb(&self) -> Self::ProtoStruct {
+                    self.to_u32().unwrap()
+                }
+
+                fn from_pb(pb: Self::ProtoStruct) -> Result<Self, failure::Error> {
+                    Self::from_u32(pb)
+                        .ok_or_else(|| format_err!("Unknown {} value: {}", stringify!($name), pb))
+                }
+            }
+        };
+    }
+
+    impl_protobuf_convert_for_enum!(CloseReason);
+    impl_protobuf_convert_for_enum!(AuctionKind);
+    impl_protobuf_convert_for_enum!(AuctionPhase);
+
     /// Auction state.
     #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
     #[exonum(pb = "crate::proto::AuctionState")]
@@ -118,17 +467,532 @@ pub mod data_layout {
         pub bidding_merkle_root: Hash,
         /// If closed => no auctions are accepted.
         pub closed: bool,
+        /// Reason why the auction was closed.
+        pub close_reason: CloseReason,
+        /// Effective end time of the current auction phase. Late bids may extend it.
+        pub ends_at: DateTime<Utc>,
+        /// Current auction phase.
+        pub phase: AuctionPhase,
+        /// Sealed-bid auctions only. Merkle root of the bid commitments.
+        pub commitments_merkle_root: Hash,
+    }
+
+    /// Key of the open auctions index. Keys are ordered by the end time first,
+    /// so the auctions due to be closed are at the beginning of the index.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct AuctionExpiry {
+        /// End time of the current auction phase.
+        pub ends_at: DateTime<Utc>,
+        /// Auction identifier.
+        pub auction_id: u64,
+    }
+
+    impl StorageKey for AuctionExpiry {
+        fn size(&self) -> usize {
+            self.ends_at.size() + self.auction_id.size()
+        }
+
+        fn write(&self, buffer: &mut [u8]) {
+            let split = self.ends_at.size();
+            self.ends_at.write(&mut buffer[..split]);
+            self.auction_id.write(&mut buffer[split..]);
+        }
+
+        fn read(buffer: &[u8]) -> Self {
+            let split = buffer.len() - 8;
+            AuctionExpiry {
+                ends_at: DateTime::<Utc>::read(&buffer[..split]),
+                auction_id: u64::read(&buffer[split..]),
+            }
+        }
+    }
+
+    impl AuctionState {
+        /// Dutch auctions only. Price at the given time, decaying from the start price
+        /// to the floor price over the auction duration.
+        pub fn dutch_price(&self, ts: DateTime<Utc>) -> u64 {
+            let auction = &self.auction;
+            let duration = cmp::max(auction.duration, 1);
+            let elapsed = cmp::max((ts - self.started_at).num_seconds(), 0) as u64;
+            let mut elapsed = cmp::min(elapsed, duration);
+            if auction.decay_interval != 0 {
+                elapsed -= elapsed % auction.decay_interval;
+            }
+
+            let price_range = u128::from(auction.start_price - auction.floor_price);
+            let discount = price_range * u128::from(elapsed) / u128::from(duration);
+            auction.start_price - discount as u64
+        }
+
+        /// Decodes the auction state stored by an earlier version of the service.
+        /// Hashes missing in the record are set to zero hashes; other missing fields
+        /// get their protobuf defaults (e.g., `ends_at` is the Unix epoch).
+        pub fn from_legacy_bytes(bytes: &[u8]) -> Result<Self, failure::Error> {
+            let mut pb: crate::proto::AuctionState = protobuf::parse_from_bytes(bytes)?;
+            if !pb.get_auction().has_reserve_commitment() {
+                pb.mut_auction()
+                    .set_reserve_commitment(Hash::zero().to_pb());
+            }
+            if !pb.has_commitments_merkle_root() {
+                pb.set_commitments_merkle_root(Hash::zero().to_pb());
+            }
+            AuctionState::from_pb(pb)
+        }
+    }
+
+    /// Commitment to a sealed bid.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::SealedBid")]
+    pub struct SealedBid {
+        /// Bidder is some participant identified by their public key.
+        pub public_key: PublicKey,
+        /// Hash of the bid value, the secret salt and the bidder key.
+        pub commitment: Hash,
+        /// Funds reserved by the bidder. Revealed bid can not exceed the deposit.
+        pub deposit: u64,
+        /// If revealed => `value` contains the bid value.
+        pub revealed: bool,
+        /// Revealed value of the bid.
+        pub value: u64,
+    }
+
+    /// Information about fixed-price listing.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::Listing")]
+    pub struct Listing {
+        /// Participant selling the owl.
+        pub public_key: PublicKey,
+        /// Owl (or egg) with `owl_id` is listed.
+        pub owl_id: Hash,
+        /// Price of the owl.
+        pub price: u64,
+    }
+
+    /// Fixed-price listing state.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::ListingState")]
+    pub struct ListingState {
+        /// Listing identifier.
+        pub id: u64,
+        /// Listing information.
+        pub listing: Listing,
+        /// Time when the owl was listed.
+        pub listed_at: DateTime<Utc>,
+        /// If closed => the owl can not be bought.
+        pub closed: bool,
+        /// Reason why the listing was closed. Delisted owls are marked as `Cancelled`.
+        pub close_reason: CloseReason,
+    }
+
+    /// Offer of the owl for breeding with owls of other users.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::StudOffer")]
+    pub struct StudOffer {
+        /// Offered owl.
+        pub owl_id: Hash,
+        /// Owner of the owl.
+        pub public_key: PublicKey,
+        /// Fee paid to the owner for each breeding.
+        pub price: u64,
+        /// Time when the owl was offered.
+        pub offered_at: DateTime<Utc>,
+    }
+
+    /// Number of owls with each trait. Body part counts are indexed by the variant.
+    ///
+    /// Color is not counted, since almost every owl has a unique color.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::TraitStats")]
+    pub struct TraitStats {
+        /// Total number of owls.
+        pub owls: u64,
+        /// Number of owls with each eye shape.
+        pub eyes: Vec<u64>,
+        /// Number of owls with each wings shape.
+        pub wings: Vec<u64>,
+        /// Number of owls with each chest pattern.
+        pub chest: Vec<u64>,
+        /// Number of owls with each tail shape.
+        pub tail: Vec<u64>,
+    }
+
+    impl TraitStats {
+        /// Statistics without owls.
+        pub fn new() -> Self {
+            let variants = vec![0; BODY_PART_VARIANTS as usize];
+            TraitStats {
+                owls: 0,
+                eyes: variants.clone(),
+                wings: variants.clone(),
+                chest: variants.clone(),
+                tail: variants,
+            }
+        }
+
+        /// Counts the traits of a new owl.
+        pub fn add(&mut self, traits: &Traits) {
+            self.owls += 1;
+            self.eyes[traits.eyes as usize] += 1;
+            self.wings[traits.wings as usize] += 1;
+            self.chest[traits.chest as usize] += 1;
+            self.tail[traits.tail as usize] += 1;
+        }
+
+        /// Rarity score of the owl with the given traits. The score is a sum of inverse
+        /// frequencies of its body parts, so the rarer the traits, the higher the score.
+        pub fn rarity(&self, traits: &Traits) -> f64 {
+            let counts = [
+                self.eyes[traits.eyes as usize],
+                self.wings[traits.wings as usize],
+                self.chest[traits.chest as usize],
+                self.tail[traits.tail as usize],
+            ];
+            counts
+                .iter()
+                .filter(|count| **count > 0)
+                .map(|count| self.owls as f64 / *count as f64)
+                .sum()
+        }
+    }
+
+    impl Default for TraitStats {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// Kind of the event in the user history.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Primitive)]
+    pub enum EventKind {
+        /// Owl was born: the starter pack was given to the user or the egg was hatched.
+        OwlBorn = 0,
+        /// Egg was laid by breeding.
+        EggLaid = 1,
+        /// Owl (or egg) was bought from `counterparty` for `amount`
+        /// by the listing or at the auction.
+        OwlBought = 2,
+        /// Owl (or egg) was sold to `counterparty` for `amount`.
+        OwlSold = 3,
+        /// Owl (or egg) was given by `counterparty`.
+        OwlReceived = 4,
+        /// Owl (or egg) was given to `counterparty`.
+        OwlGiven = 5,
+        /// Bid of `amount` was placed (or the sealed bid was revealed) in the auction.
+        BidPlaced = 6,
+        /// Sealed bid was committed with the deposit of `amount`.
+        BidCommitted = 7,
+        /// Bid was outbid by `counterparty` with the bid of `amount`.
+        /// Funds reserved for the bid are released.
+        Outbid = 8,
+        /// Funds of `amount` were issued.
+        FundsIssued = 9,
+        /// Funds of `amount` were sent to `counterparty`.
+        FundsSent = 10,
+        /// Funds of `amount` were received from `counterparty`.
+        FundsReceived = 11,
+        /// Owl was put up for the auction with the start price of `amount`.
+        AuctionCreated = 12,
+        /// Auction of the user (or the auction the user has funds reserved in)
+        /// was closed for the `close_reason`.
+        AuctionClosed = 13,
+        /// Owl (or egg) was listed for sale for `amount`.
+        OwlListed = 14,
+        /// Owl (or egg) was withdrawn from sale.
+        OwlDelisted = 15,
+        /// Owl was offered for breeding for the fee of `amount`.
+        StudOffered = 16,
+        /// Stud offer was withdrawn.
+        StudWithdrawn = 17,
+        /// Stud fee of `amount` was paid to `counterparty`.
+        StudFeePaid = 18,
+        /// Stud fee of `amount` was received from `counterparty`.
+        StudFeeReceived = 19,
+        /// Owl was renamed.
+        OwlRenamed = 20,
+        /// User profile was updated.
+        ProfileUpdated = 21,
+        /// Reserve price of `amount` was revealed.
+        ReserveRevealed = 22,
+    }
+
+    impl_protobuf_convert_for_enum!(EventKind);
+
+    /// Event in the user history. Fields which do not apply to the event kind are empty.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::UserEvent")]
+    pub struct UserEvent {
+        /// Kind of the event.
+        pub kind: EventKind,
+        /// Height of the block with the event.
+        pub height: u64,
+        /// Transaction which caused the event, or zero hash if the event happened
+        /// when the block was committed (e.g., the auction time was over).
+        pub tx_hash: Hash,
+        /// Owl (or egg) concerned.
+        pub owl_id: Hash,
+        /// Auction concerned.
+        pub auction_id: u64,
+        /// Other party of the deal.
+        pub counterparty: PublicKey,
+        /// Funds concerned: price, bid, fee or transferred amount.
+        pub amount: u64,
+        /// Reason why the auction was closed.
+        pub close_reason: CloseReason,
+    }
+
+    impl UserEvent {
+        /// Event of the given kind caused by the transaction with the given hash.
+        /// Height is set when the event is recorded.
+        pub fn new(kind: EventKind, tx_hash: Hash) -> Self {
+            UserEvent {
+                kind,
+                height: 0,
+                tx_hash,
+                owl_id: Hash::zero(),
+                auction_id: 0,
+                counterparty: PublicKey::zero(),
+                amount: 0,
+                close_reason: CloseReason::Open,
+            }
+        }
+
+        /// Sets the owl (or egg) concerned.
+        pub fn with_owl(mut self, owl_id: Hash) -> Self {
+            self.owl_id = owl_id;
+            self
+        }
+
+        /// Sets the auction concerned.
+        pub fn with_auction(mut self, auction_id: u64) -> Self {
+            self.auction_id = auction_id;
+            self
+        }
+
+        /// Sets the other party of the deal.
+        pub fn with_counterparty(mut self, counterparty: PublicKey) -> Self {
+            self.counterparty = counterparty;
+            self
+        }
+
+        /// Sets the funds concerned.
+        pub fn with_amount(mut self, amount: u64) -> Self {
+            self.amount = amount;
+            self
+        }
+
+        /// Sets the reason why the auction was closed.
+        pub fn with_close_reason(mut self, close_reason: CloseReason) -> Self {
+            self.close_reason = close_reason;
+            self
+        }
+    }
+}
+
+/// Appearance of owls encoded in their DNA.
+///
+/// Traits are read the same way as `splitDNA` in the frontend does, from the DNA serialized
+/// as the `exonum.DNA` protobuf message: the tag of its `fixed32` field followed by the DNA
+/// in little-endian order. The first three bytes of the message are the color of the owl
+/// in RGB, so the red component is always `DNA_FIELD_TAG`. The fourth byte is split into
+/// 2-bit body parts, starting from the most significant bits: eyes, wings, chest and tail.
+/// The most significant byte of the DNA does not affect the appearance.
+pub mod genetics {
+    use serde_derive::{Deserialize, Serialize};
+
+    use crate::data_layout::CryptoOwl;
+
+    /// Number of variants of each body part.
+    pub const BODY_PART_VARIANTS: u8 = 4;
+
+    /// Tag of the `dna` field (number 1, wire type `fixed32`) in the serialized DNA.
+    pub const DNA_FIELD_TAG: u8 = 0x0d;
+
+    /// Color of the owl.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub struct Color {
+        /// Red component.
+        pub red: u8,
+        /// Green component.
+        pub green: u8,
+        /// Blue component.
+        pub blue: u8,
+    }
+
+    impl Color {
+        /// Hexadecimal representation of the color, as used in SVG (without `#`).
+        pub fn to_hex(&self) -> String {
+            format!("{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+        }
+    }
+
+    /// Traits of the owl decoded from its DNA.
+    ///
+    /// Each body part is a variant number in range `0..BODY_PART_VARIANTS`.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub struct Traits {
+        /// Feather color.
+        pub color: Color,
+        /// Eye shape.
+        pub eyes: u8,
+        /// Wings shape.
+        pub wings: u8,
+        /// Chest pattern.
+        pub chest: u8,
+        /// Tail shape.
+        pub tail: u8,
+    }
+
+    impl Traits {
+        /// Decodes traits from the DNA.
+        pub fn from_dna(dna: u32) -> Self {
+            let appearance = (dna >> 16) as u8;
+            Traits {
+                color: Color {
+                    red: DNA_FIELD_TAG,
+                    green: dna as u8,
+                    blue: (dna >> 8) as u8,
+                },
+                eyes: appearance >> 6,
+                wings: (appearance >> 4) & 0b11,
+                chest: (appearance >> 2) & 0b11,
+                tail: appearance & 0b11,
+            }
+        }
+
+        /// Encodes traits back into the DNA with the most significant byte set to zero.
+        /// The red component is not encoded, and body part variants out of range
+        /// are truncated to their lower bits.
+        pub fn to_dna(&self) -> u32 {
+            let appearance = (self.eyes & 0b11) << 6
+                | (self.wings & 0b11) << 4
+                | (self.chest & 0b11) << 2
+                | (self.tail & 0b11);
+            u32::from(self.color.green)
+                | u32::from(self.color.blue) << 8
+                | u32::from(appearance) << 16
+        }
+    }
+
+    impl From<&CryptoOwl> for Traits {
+        fn from(owl: &CryptoOwl) -> Self {
+            Traits::from_dna(owl.dna)
+        }
+    }
+}
+
+/// Server-side rendering of owl images.
+pub mod render {
+    use lazy_static::lazy_static;
+
+    use crate::genetics::Traits;
+
+    /// MIME type of rendered images.
+    pub const SVG_CONTENT_TYPE: &str = "image/svg+xml";
+
+    /// Owl image drawn by `OwlIcon.vue` with placeholders for the color of the feathers
+    /// (`{{color}}`) and for the display of each body part variant (`{{eyes_0}}` and so on).
+    /// Generated by `npm run owl-fixtures` in the `frontend` directory.
+    const OWL_TEMPLATE: &str = include_str!("owl.svg");
+
+    /// Body parts in the order of `Traits` fields.
+    const BODY_PARTS: [&str; 4] = ["eyes", "wings", "chest", "tail"];
+
+    lazy_static! {
+        static ref SEGMENTS: Vec<Segment> = parse_template(OWL_TEMPLATE);
+    }
+
+    /// Part of the template.
+    enum Segment {
+        /// Text copied as is.
+        Text(&'static str),
+        /// Color of the feathers.
+        Color,
+        /// Display of the body part variant, `inline` if the owl has it and `none` otherwise.
+        Display { part: usize, variant: u8 },
+    }
+
+    /// Renders the owl with the given DNA into the same SVG image as `OwlIcon.vue` does.
+    pub fn owl_svg(dna: u32) -> String {
+        let traits = Traits::from_dna(dna);
+        let color = format!("#{}", traits.color.to_hex());
+        let variants = [traits.eyes, traits.wings, traits.chest, traits.tail];
+
+        let mut svg = String::with_capacity(OWL_TEMPLATE.len());
+        for segment in SEGMENTS.iter() {
+            match *segment {
+                Segment::Text(text) => svg.push_str(text),
+                Segment::Color => svg.push_str(&color),
+                Segment::Display { part, variant } if variants[part] == variant => {
+                    svg.push_str("inline")
+                }
+                Segment::Display { .. } => svg.push_str("none"),
+            }
+        }
+        svg
+    }
+
+    /// Splits the template at the placeholders.
+    fn parse_template(template: &'static str) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            let end = start + rest[start..].find("}}").expect("Unclosed placeholder");
+            segments.push(Segment::Text(&rest[..start]));
+            segments.push(parse_placeholder(&rest[start + 2..end]));
+            rest = &rest[end + 2..];
+        }
+        segments.push(Segment::Text(rest));
+        segments
+    }
+
+    fn parse_placeholder(name: &str) -> Segment {
+        if name == "color" {
+            return Segment::Color;
+        }
+        let mut parts = name.splitn(2, '_');
+        let part = parts
+            .next()
+            .and_then(|part| BODY_PARTS.iter().position(|p| *p == part));
+        let variant = parts.next().and_then(|variant| variant.parse().ok());
+        match (part, variant) {
+            (Some(part), Some(variant)) => Segment::Display { part, variant },
+            _ => panic!("Unknown placeholder {{{{{}}}}} in the owl template", name),
+        }
     }
 }
 
 /// Database schema.
 pub mod schema {
+    use log::error;
+
+    use exonum::blockchain::Schema;
     use exonum::crypto::{Hash, PublicKey};
+    use exonum::helpers::Height;
     use exonum::storage::{
-        Fork, ListIndex, MapIndex, ProofListIndex, ProofMapIndex, Snapshot, ValueSetIndex,
+        Entry, Fork, KeySetIndex, ListIndex, MapIndex, ProofListIndex, ProofMapIndex, Snapshot,
+        ValueSetIndex,
+    };
+
+    use crate::{
+        config::CryptoOwlsConfig,
+        data_layout::{
+            AuctionExpiry, AuctionState, Bid, CryptoOwlState, Egg, EggHatching, EggIncubation,
+            ListingState, OwlMetadata, SealedBid, StudOffer, TraitStats, User, UserEvent,
+        },
+        CRYPTOOWLS_SERVICE_NAME,
     };
 
-    use crate::data_layout::{AuctionState, Bid, CryptoOwlState, User};
+    /// Index of the `users` table root in `CryptoOwlsSchema::state_hash`.
+    pub const USERS_TABLE_INDEX: usize = 0;
+
+    /// Index of the `owls_state` table root in `CryptoOwlsSchema::state_hash`.
+    pub const OWLS_STATE_TABLE_INDEX: usize = 1;
+
+    /// Index of the `auctions` table root in `CryptoOwlsSchema::state_hash`.
+    pub const AUCTIONS_TABLE_INDEX: usize = 2;
+
+    /// Index of the `user_history_roots` table root in `CryptoOwlsSchema::state_hash`.
+    pub const USER_HISTORY_ROOTS_TABLE_INDEX: usize = 8;
 
     pub struct CryptoOwlsSchema<T> {
         pub view: T,
@@ -143,6 +1007,42 @@ pub mod schema {
             CryptoOwlsSchema { view }
         }
 
+        /// Service configuration from the actual consensus configuration.
+        /// Default values are used if the service section is missing. The configuration
+        /// service does not check service sections of the proposals, so if the section
+        /// is invalid, the latest valid one of the earlier configurations is used.
+        pub fn config(&self) -> CryptoOwlsConfig {
+            let schema = Schema::new(&self.view);
+            let next_height = Height(schema.block_hashes_by_height().len());
+            let configs_actual_from = schema.configs_actual_from();
+            for i in (0..configs_actual_from.len()).rev() {
+                let reference = configs_actual_from.get(i).unwrap();
+                if reference.actual_from() > next_height {
+                    continue;
+                }
+                let configuration = schema
+                    .configuration_by_hash(reference.cfg_hash())
+                    .expect("Configuration is absent in configs table");
+                let value = match configuration.services.get(CRYPTOOWLS_SERVICE_NAME) {
+                    Some(value) => value.clone(),
+                    None => return CryptoOwlsConfig::default(),
+                };
+                let config = serde_json::from_value::<CryptoOwlsConfig>(value)
+                    .map_err(failure::Error::from)
+                    .and_then(|config| config.validate().map(|_| config));
+                match config {
+                    Ok(config) => return config,
+                    Err(e) => error!(
+                        "Invalid {} configuration actual from height {}: {}",
+                        CRYPTOOWLS_SERVICE_NAME,
+                        reference.actual_from(),
+                        e
+                    ),
+                }
+            }
+            CryptoOwlsConfig::default()
+        }
+
         /// Users.
         pub fn users(&self) -> ProofMapIndex<&T, PublicKey, User> {
             ProofMapIndex::new("cryptoowls.users", &self.view)
@@ -153,21 +1053,52 @@ pub mod schema {
             ProofMapIndex::new("cryptoowls.owls_state", &self.view)
         }
 
+        /// Eggs waiting to be hatched.
+        pub fn eggs(&self) -> ProofMapIndex<&T, Hash, Egg> {
+            ProofMapIndex::new("cryptoowls.eggs", &self.view)
+        }
+
+        /// Owls metadata: parents, generation and time of the birth.
+        pub fn owls_metadata(&self) -> ProofMapIndex<&T, Hash, OwlMetadata> {
+            ProofMapIndex::new("cryptoowls.owls_metadata", &self.view)
+        }
+
+        /// Helper table for linking owl and its children.
+        pub fn owl_children(&self, owl_id: &Hash) -> ValueSetIndex<&T, Hash> {
+            ValueSetIndex::new_in_family("cryptoowls.owl_children", owl_id, &self.view)
+        }
+
         /// Owl auctions.
         pub fn auctions(&self) -> ProofListIndex<&T, AuctionState> {
             ProofListIndex::new("cryptoowls.auctions", &self.view)
         }
 
+        /// Auctions as they are stored, without decoding. Used to migrate the records
+        /// written by earlier versions of the service (see `AuctionState::from_legacy_bytes`).
+        pub fn raw_auctions(&self) -> ProofListIndex<&T, Vec<u8>> {
+            ProofListIndex::new("cryptoowls.auctions", &self.view)
+        }
+
         /// Owl auction bids.
         pub fn auction_bids(&self, auction_id: u64) -> ProofListIndex<&T, Bid> {
             ProofListIndex::new_in_family("cryptoowls.auction_bids", &auction_id, &self.view)
         }
 
+        /// Commitments to sealed bids of the auction.
+        pub fn sealed_bids(&self, auction_id: u64) -> ProofMapIndex<&T, PublicKey, SealedBid> {
+            ProofMapIndex::new_in_family("cryptoowls.sealed_bids", &auction_id, &self.view)
+        }
+
         /// Helper table for linking user and his owls.
         pub fn user_owls(&self, public_key: &PublicKey) -> ValueSetIndex<&T, Hash> {
             ValueSetIndex::new_in_family("cryptoowls.user_owls", public_key, &self.view)
         }
 
+        /// Helper table for linking user and their eggs.
+        pub fn user_eggs(&self, public_key: &PublicKey) -> ValueSetIndex<&T, Hash> {
+            ValueSetIndex::new_in_family("cryptoowls.user_eggs", public_key, &self.view)
+        }
+
         /// Helper table for linking user and his auctions.
         pub fn user_auctions(&self, public_key: &PublicKey) -> ListIndex<&T, u64> {
             ListIndex::new_in_family("cryptoowls.user_auctions", public_key, &self.view)
@@ -178,18 +1109,109 @@ pub mod schema {
             MapIndex::new("cryptoowls.owl_auctions", &self.view)
         }
 
-        /// Method to get state hash. Depends on `users`, `owls_state` and `auctions` tables.
-        pub fn state_hash(&self) -> Vec<Hash> {
-            vec![
-                self.users().merkle_root(),
-                self.owls_state().merkle_root(),
-                self.auctions().merkle_root(),
-            ]
+        /// Open auctions ordered by the end time of their current phase.
+        pub fn auction_expiry(&self) -> KeySetIndex<&T, AuctionExpiry> {
+            KeySetIndex::new("cryptoowls.auction_expiry", &self.view)
         }
-    }
 
-    /// Mutable accessors for all our tables.
-    impl<'a> CryptoOwlsSchema<&'a mut Fork> {
+        /// Eggs which are not ready to hatch ordered by the hatching time.
+        pub fn incubated_eggs(&self) -> KeySetIndex<&T, EggIncubation> {
+            KeySetIndex::new("cryptoowls.incubated_eggs", &self.view)
+        }
+
+        /// Eggs which wait for the randomness ordered by the hatching height.
+        pub fn hatching_eggs(&self) -> KeySetIndex<&T, EggHatching> {
+            KeySetIndex::new("cryptoowls.hatching_eggs", &self.view)
+        }
+
+        /// Fixed-price owl listings.
+        pub fn listings(&self) -> ProofListIndex<&T, ListingState> {
+            ProofListIndex::new("cryptoowls.listings", &self.view)
+        }
+
+        /// Helper table for linking user and his listings.
+        pub fn user_listings(&self, public_key: &PublicKey) -> ListIndex<&T, u64> {
+            ListIndex::new_in_family("cryptoowls.user_listings", public_key, &self.view)
+        }
+
+        /// Helper table for linking owl and its open listing.
+        pub fn owl_listing(&self) -> MapIndex<&T, Hash, u64> {
+            MapIndex::new("cryptoowls.owl_listings", &self.view)
+        }
+
+        /// Active stud offers by the offered owl.
+        pub fn stud_offers(&self) -> ProofMapIndex<&T, Hash, StudOffer> {
+            ProofMapIndex::new("cryptoowls.stud_offers", &self.view)
+        }
+
+        /// Helper table for linking user and their offered owls.
+        pub fn user_stud_offers(&self, public_key: &PublicKey) -> ValueSetIndex<&T, Hash> {
+            ValueSetIndex::new_in_family("cryptoowls.user_stud_offers", public_key, &self.view)
+        }
+
+        /// History of events concerning the user, in order of occurrence.
+        pub fn user_history(&self, public_key: &PublicKey) -> ProofListIndex<&T, UserEvent> {
+            ProofListIndex::new_in_family("cryptoowls.user_history", public_key, &self.view)
+        }
+
+        /// Merkle roots of the user histories. Authenticates `user_history`
+        /// in the state hash.
+        pub fn user_history_roots(&self) -> ProofMapIndex<&T, PublicKey, Hash> {
+            ProofMapIndex::new("cryptoowls.user_history_roots", &self.view)
+        }
+
+        /// Owl (or egg) identifiers by their names. Keys are hashes of normalized names
+        /// (see `transactions::owl_name_key`).
+        pub fn owl_names(&self) -> ProofMapIndex<&T, Hash, Hash> {
+            ProofMapIndex::new("cryptoowls.owl_names", &self.view)
+        }
+
+        /// Version of the stored data layout (see `SCHEMA_VERSION`).
+        pub fn schema_version(&self) -> Entry<&T, u32> {
+            Entry::new("cryptoowls.schema_version", &self.view)
+        }
+
+        /// Statistics of owl traits. Updated each time a new owl is born.
+        pub fn trait_stats(&self) -> Entry<&T, TraitStats> {
+            Entry::new("cryptoowls.trait_stats", &self.view)
+        }
+
+        /// Method to get state hash. Depends on `users`, `owls_state`, `auctions`,
+        /// `listings`, `eggs`, `owls_metadata`, `stud_offers`, `owl_names`
+        /// and `user_history_roots` tables.
+        ///
+        /// Other tables are authenticated indirectly or not at all:
+        ///
+        /// - `auction_bids` and `sealed_bids` of the auction are authenticated through
+        ///   `bidding_merkle_root` and `commitments_merkle_root` of its `AuctionState`.
+        /// - Helper tables (`user_owls`, `user_eggs`, `user_auctions`, `user_listings`,
+        ///   `user_stud_offers`, `owl_auction`, `owl_listing`, `owl_children` and
+        ///   `auction_expiry`) are not authenticated. They index the authenticated tables,
+        ///   so a client verifies the indexed facts there: the owner of the owl
+        ///   in `owls_state`, the seller and the state of the auction in `auctions`, etc.
+        /// - `user_history` of the user is authenticated through its Merkle root
+        ///   in `user_history_roots`.
+        /// - `trait_stats` and `schema_version` are not authenticated.
+        ///
+        /// New tables are appended, so that proofs to the tables (see `USERS_TABLE_INDEX`)
+        /// stay valid.
+        pub fn state_hash(&self) -> Vec<Hash> {
+            vec![
+                self.users().merkle_root(),
+                self.owls_state().merkle_root(),
+                self.auctions().merkle_root(),
+                self.listings().merkle_root(),
+                self.eggs().merkle_root(),
+                self.owls_metadata().merkle_root(),
+                self.stud_offers().merkle_root(),
+                self.owl_names().merkle_root(),
+                self.user_history_roots().merkle_root(),
+            ]
+        }
+    }
+
+    /// Mutable accessors for all our tables.
+    impl<'a> CryptoOwlsSchema<&'a mut Fork> {
         pub fn users_mut(&mut self) -> ProofMapIndex<&mut Fork, PublicKey, User> {
             ProofMapIndex::new("cryptoowls.users", self.view)
         }
@@ -198,6 +1220,18 @@ pub mod schema {
             ProofMapIndex::new("cryptoowls.owls_state", self.view)
         }
 
+        pub fn eggs_mut(&mut self) -> ProofMapIndex<&mut Fork, Hash, Egg> {
+            ProofMapIndex::new("cryptoowls.eggs", self.view)
+        }
+
+        pub fn owls_metadata_mut(&mut self) -> ProofMapIndex<&mut Fork, Hash, OwlMetadata> {
+            ProofMapIndex::new("cryptoowls.owls_metadata", self.view)
+        }
+
+        pub fn owl_children_mut(&mut self, owl_id: &Hash) -> ValueSetIndex<&mut Fork, Hash> {
+            ValueSetIndex::new_in_family("cryptoowls.owl_children", owl_id, self.view)
+        }
+
         pub fn auctions_mut(&mut self) -> ProofListIndex<&mut Fork, AuctionState> {
             ProofListIndex::new("cryptoowls.auctions", self.view)
         }
@@ -206,10 +1240,21 @@ pub mod schema {
             ProofListIndex::new_in_family("cryptoowls.auction_bids", &auction_id, self.view)
         }
 
+        pub fn sealed_bids_mut(
+            &mut self,
+            auction_id: u64,
+        ) -> ProofMapIndex<&mut Fork, PublicKey, SealedBid> {
+            ProofMapIndex::new_in_family("cryptoowls.sealed_bids", &auction_id, self.view)
+        }
+
         pub fn user_owls_mut(&mut self, public_key: &PublicKey) -> ValueSetIndex<&mut Fork, Hash> {
             ValueSetIndex::new_in_family("cryptoowls.user_owls", public_key, self.view)
         }
 
+        pub fn user_eggs_mut(&mut self, public_key: &PublicKey) -> ValueSetIndex<&mut Fork, Hash> {
+            ValueSetIndex::new_in_family("cryptoowls.user_eggs", public_key, self.view)
+        }
+
         pub fn user_auctions_mut(&mut self, public_key: &PublicKey) -> ListIndex<&mut Fork, u64> {
             ListIndex::new_in_family("cryptoowls.user_auctions", public_key, self.view)
         }
@@ -217,14 +1262,73 @@ pub mod schema {
         pub fn owl_auction_mut(&mut self) -> MapIndex<&mut Fork, Hash, u64> {
             MapIndex::new("cryptoowls.owl_auctions", self.view)
         }
+
+        pub fn auction_expiry_mut(&mut self) -> KeySetIndex<&mut Fork, AuctionExpiry> {
+            KeySetIndex::new("cryptoowls.auction_expiry", self.view)
+        }
+
+        pub fn incubated_eggs_mut(&mut self) -> KeySetIndex<&mut Fork, EggIncubation> {
+            KeySetIndex::new("cryptoowls.incubated_eggs", self.view)
+        }
+
+        pub fn hatching_eggs_mut(&mut self) -> KeySetIndex<&mut Fork, EggHatching> {
+            KeySetIndex::new("cryptoowls.hatching_eggs", self.view)
+        }
+
+        pub fn listings_mut(&mut self) -> ProofListIndex<&mut Fork, ListingState> {
+            ProofListIndex::new("cryptoowls.listings", self.view)
+        }
+
+        pub fn user_listings_mut(&mut self, public_key: &PublicKey) -> ListIndex<&mut Fork, u64> {
+            ListIndex::new_in_family("cryptoowls.user_listings", public_key, self.view)
+        }
+
+        pub fn owl_listing_mut(&mut self) -> MapIndex<&mut Fork, Hash, u64> {
+            MapIndex::new("cryptoowls.owl_listings", self.view)
+        }
+
+        pub fn stud_offers_mut(&mut self) -> ProofMapIndex<&mut Fork, Hash, StudOffer> {
+            ProofMapIndex::new("cryptoowls.stud_offers", self.view)
+        }
+
+        pub fn user_stud_offers_mut(
+            &mut self,
+            public_key: &PublicKey,
+        ) -> ValueSetIndex<&mut Fork, Hash> {
+            ValueSetIndex::new_in_family("cryptoowls.user_stud_offers", public_key, self.view)
+        }
+
+        pub fn user_history_mut(
+            &mut self,
+            public_key: &PublicKey,
+        ) -> ProofListIndex<&mut Fork, UserEvent> {
+            ProofListIndex::new_in_family("cryptoowls.user_history", public_key, self.view)
+        }
+
+        pub fn user_history_roots_mut(&mut self) -> ProofMapIndex<&mut Fork, PublicKey, Hash> {
+            ProofMapIndex::new("cryptoowls.user_history_roots", self.view)
+        }
+
+        pub fn owl_names_mut(&mut self) -> ProofMapIndex<&mut Fork, Hash, Hash> {
+            ProofMapIndex::new("cryptoowls.owl_names", self.view)
+        }
+
+        pub fn trait_stats_mut(&mut self) -> Entry<&mut Fork, TraitStats> {
+            Entry::new("cryptoowls.trait_stats", self.view)
+        }
+
+        pub fn schema_version_mut(&mut self) -> Entry<&mut Fork, u32> {
+            Entry::new("cryptoowls.schema_version", self.view)
+        }
     }
 }
 
 /// Module with description of all transactions.
 pub mod transactions {
+    use std::cmp;
     use std::io::Cursor;
 
-    use byteorder::{BigEndian, ReadBytesExt};
+    use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
     use chrono::{DateTime, Duration, Utc};
     use enum_primitive_derive::Primitive;
     use failure_derive::Fail;
@@ -236,14 +1340,15 @@ pub mod transactions {
     use exonum::blockchain::{
         ExecutionError, ExecutionResult, Schema, Transaction, TransactionContext,
     };
-    use exonum::crypto::{CryptoHash, Hash, PublicKey};
+    use exonum::crypto::{self, CryptoHash, Hash, PublicKey};
+    use exonum::helpers::Height;
     use exonum::storage::{Fork, Snapshot};
     use exonum_derive::{ProtobufConvert, TransactionSet};
     use exonum_time::schema::TimeSchema;
 
     use crate::{
-        data_layout::*, schema::CryptoOwlsSchema, BREEDING_PRICE, BREEDING_TIMEOUT, ISSUE_AMOUNT,
-        ISSUE_TIMEOUT,
+        data_layout::*, genetics::Traits, schema::CryptoOwlsSchema, INBREEDING_DEPTH,
+        MUTATION_RATE, SCHEMA_VERSION,
     };
 
     //     use byteorder::{BigEndian, ReadBytesExt};
@@ -307,6 +1412,24 @@ pub mod transactions {
         /// Type `Duration` is not used because
         /// the trait `ProtobufConvert` is not implemented for `chrono::Duration`.
         pub duration: u64,
+        /// If set, the seller may cancel the auction even if it has bids.
+        pub cancellable_with_bids: bool,
+        /// Price at which the auction is settled immediately. Zero if not set.
+        pub buyout_price: u64,
+        /// Auction kind.
+        pub kind: AuctionKind,
+        /// Sealed-bid auctions and auctions with a reserve price only.
+        /// Duration of the reveal phase in seconds.
+        pub reveal_duration: u64,
+        /// Dutch auctions only. Price reached at the end of the auction.
+        pub floor_price: u64,
+        /// Dutch auctions only. Price drops every `decay_interval` seconds.
+        /// Zero if the price drops linearly.
+        pub decay_interval: u64,
+        /// Hash of the minimal price the seller agrees to sell the owl for, the secret salt
+        /// and the seller key (see `reserve_price_commitment`), or zero hash if not set.
+        /// The reserve price is revealed by `RevealReserve` after bidding is over.
+        pub reserve_commitment: Hash,
     }
 
     #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
@@ -318,6 +1441,151 @@ pub mod transactions {
         pub value: u64,
     }
 
+    /// Transaction to give an owl (or an egg) to another user.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::TransferOwl")]
+    pub struct TransferOwl {
+        /// Owl (or egg) with `owl_id` is transferred.
+        pub owl_id: Hash,
+        /// Receiver of the owl.
+        pub to: PublicKey,
+    }
+
+    /// Transaction to transfer funds to another user.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::Transfer")]
+    pub struct Transfer {
+        /// Receiver of the funds.
+        pub to: PublicKey,
+        /// Amount of funds to transfer.
+        pub amount: u64,
+    }
+
+    /// Transaction to cancel an open auction by its seller.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::CancelAuction")]
+    pub struct CancelAuction {
+        /// Auction ID to cancel.
+        pub auction_id: u64,
+    }
+
+    /// Transaction to list an owl (or an egg) for sale at a fixed price.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::ListOwl")]
+    pub struct ListOwl {
+        /// Owl (or egg) with `owl_id` is listed.
+        pub owl_id: Hash,
+        /// Price of the owl.
+        pub price: u64,
+    }
+
+    /// Transaction to buy a listed owl.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::BuyOwl")]
+    pub struct BuyOwl {
+        /// Listing ID to buy.
+        pub listing_id: u64,
+    }
+
+    /// Transaction to withdraw an owl from sale.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::DelistOwl")]
+    pub struct DelistOwl {
+        /// Listing ID to close.
+        pub listing_id: u64,
+    }
+
+    /// Transaction to commit to a sealed bid.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::CommitBid")]
+    pub struct CommitBid {
+        /// Sealed-bid auction ID where a bid must be made.
+        pub auction_id: u64,
+        /// Hash of the bid value, the secret salt and the bidder key
+        /// (see `sealed_bid_commitment`).
+        pub commitment: Hash,
+        /// Funds to reserve. Should cover the bid value.
+        pub deposit: u64,
+    }
+
+    /// Transaction to reveal a sealed bid.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::RevealBid")]
+    pub struct RevealBid {
+        /// Sealed-bid auction ID where the bid was committed.
+        pub auction_id: u64,
+        /// Bid value.
+        pub value: u64,
+        /// Secret salt used in the commitment.
+        pub salt: Hash,
+    }
+
+    /// Transaction to offer an owl for breeding with owls of other users.
+    /// Offering the owl again changes the price.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::OfferStud")]
+    pub struct OfferStud {
+        /// Owl to offer.
+        pub owl_id: Hash,
+        /// Fee paid to the owner for each breeding.
+        pub price: u64,
+    }
+
+    /// Transaction to withdraw the stud offer.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::WithdrawStud")]
+    pub struct WithdrawStud {
+        /// Offered owl.
+        pub owl_id: Hash,
+    }
+
+    /// Transaction to breed an own owl with the offered owl of another user.
+    /// The egg goes to the author, the stud fee goes to the owner of the stud.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::BreedWithStud")]
+    pub struct BreedWithStud {
+        /// Owl name.
+        pub name: String,
+        /// Offered owl which becomes the father.
+        pub stud_id: Hash,
+        /// Mother identifier.
+        pub mother_id: Hash,
+        /// Stud fee the author agrees to pay. Should match the offer.
+        pub fee: u64,
+        /// Timestamp. Is required to breed owls with the same identifiers.
+        pub seed: DateTime<Utc>,
+    }
+
+    /// Transaction to rename an owl. The owl keeps its identifier.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::RenameOwl")]
+    pub struct RenameOwl {
+        /// Owl to rename.
+        pub owl_id: Hash,
+        /// New name.
+        pub name: String,
+    }
+
+    /// Transaction to update the user profile.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::UpdateProfile")]
+    pub struct UpdateProfile {
+        /// New user name.
+        pub name: String,
+    }
+
+    /// Transaction to reveal the reserve price of the auction by its seller.
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ProtobufConvert)]
+    #[exonum(pb = "crate::proto::RevealReserve")]
+    pub struct RevealReserve {
+        /// Auction ID where the reserve price was committed.
+        pub auction_id: u64,
+        /// Reserve price.
+        pub reserve_price: u64,
+        /// Secret salt used in the commitment.
+        pub salt: Hash,
+    }
+
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TransactionSet)]
     /// Crypto owls transactions.
     pub enum Transactions {
@@ -331,11 +1599,39 @@ pub mod transactions {
         MakeBid(MakeBid),
         /// Issue transaction.
         Issue(Issue),
+        /// Transfer owl transaction.
+        TransferOwl(TransferOwl),
+        /// Transfer funds transaction.
+        Transfer(Transfer),
+        /// Cancel auction transaction.
+        CancelAuction(CancelAuction),
+        /// List owl for sale transaction.
+        ListOwl(ListOwl),
+        /// Buy listed owl transaction.
+        BuyOwl(BuyOwl),
+        /// Withdraw owl from sale transaction.
+        DelistOwl(DelistOwl),
+        /// Commit sealed bid transaction.
+        CommitBid(CommitBid),
+        /// Reveal sealed bid transaction.
+        RevealBid(RevealBid),
+        /// Offer owl for breeding transaction.
+        OfferStud(OfferStud),
+        /// Withdraw stud offer transaction.
+        WithdrawStud(WithdrawStud),
+        /// Breed with offered owl transaction.
+        BreedWithStud(BreedWithStud),
+        /// Rename owl transaction.
+        RenameOwl(RenameOwl),
+        /// Update user profile transaction.
+        UpdateProfile(UpdateProfile),
+        /// Reveal reserve price transaction.
+        RevealReserve(RevealReserve),
     }
 
     impl Transaction for CreateUser {
         fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
-            let ts = current_time(context.fork()).unwrap();
+            let ts = current_time(context.fork()).ok_or_else(|| ErrorKind::TimeNotAvailable)?;
 
             let state_hash = {
                 let info_schema = Schema::new(context.fork());
@@ -343,6 +1639,7 @@ pub mod transactions {
             };
 
             let author = context.author();
+            let tx_hash = context.tx_hash();
             let mut schema = CryptoOwlsSchema::new(context.fork());
 
             // Reject tx if the user with the same public key is already exists.
@@ -350,24 +1647,47 @@ pub mod transactions {
                 return Err(ErrorKind::UserAlreadyRegistered.into());
             }
 
+            if self.name.trim().is_empty() {
+                return Err(ErrorKind::EmptyName.into());
+            }
+
             let user = User {
                 public_key: author,
                 name: self.name.clone(),
-                balance: ISSUE_AMOUNT,
+                balance: schema.config().issue_amount,
                 reserved: 0,
                 last_fillup: ts,
             };
+            let issued = user.balance;
             schema.users_mut().put(&author, user);
+            schema.record_event(
+                &author,
+                UserEvent::new(EventKind::FundsIssued, tx_hash).with_amount(issued),
+            );
 
-            // New user gets 2 random owls.
+            // New user gets 2 random owls. Users may have the same names,
+            // so the names of the owls are made unique.
             let starter_pack = vec![
-                schema.make_uniq_owl((1, 0), &format!("{}'s Adam", self.name), &state_hash),
+                schema.make_uniq_owl(
+                    (1, 0),
+                    &schema.unique_owl_name(&format!("{}'s Adam", self.name)),
+                    &state_hash,
+                    MUTATION_RATE,
+                ),
                 schema.make_uniq_owl(
                     (1, 100_042),
-                    &format!("{}'s Eve", self.name),
+                    &schema.unique_owl_name(&format!("{}'s Eve", self.name)),
                     &author.hash(),
+                    MUTATION_RATE,
                 ),
             ];
+            for owl in &starter_pack {
+                schema.record_birth(&owl.hash(), &Hash::zero(), &Hash::zero(), 0, ts);
+                schema.record_event(
+                    &author,
+                    UserEvent::new(EventKind::OwlBorn, tx_hash).with_owl(owl.hash()),
+                );
+            }
             schema.refresh_owls(&author, starter_pack, ts);
             Ok(())
         }
@@ -376,15 +1696,20 @@ pub mod transactions {
     impl Transaction for MakeOwl {
         fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
             let author = context.author();
-            let ts = current_time(context.fork()).unwrap();
+            let egg_id = context.tx_hash();
+            let ts = current_time(context.fork()).ok_or_else(|| ErrorKind::TimeNotAvailable)?;
 
-            let state_hash = {
-                let info_schema = Schema::new(context.fork());
-                info_schema.state_hash_aggregator().merkle_root()
-            };
+            // The egg is laid in the block which is being created now.
+            let height = Schema::new(context.fork()).height().next();
 
             let mut schema = CryptoOwlsSchema::new(context.fork());
 
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+
             // Find mother and father.
             // If someone is missed will get None response.
             // Reject transaction if mother or father is not found.
@@ -394,38 +1719,53 @@ pub mod transactions {
                 .collect::<Option<Vec<CryptoOwlState>>>()
                 .ok_or_else(|| ErrorKind::OwlNotFound)?;
 
-            let user = schema.users().get(&author).unwrap();
-
             // Check if user owns these owls.
             if parents.iter().any(|p| p.owner != user.public_key) {
                 return Err(ErrorKind::AccessViolation.into());
             }
 
-            let (mother, father) = (parents[0].owl.clone(), parents[1].owl.clone());
-            // Can not use the same owl as mother and father at the same time.
-            if mother == father {
-                return Err(ErrorKind::SelfBreeding.into());
+            let (mother, father) = (&parents[0], &parents[1]);
+            schema.ensure_can_breed(father, mother, ts)?;
+
+            // Reject tx if the name is empty or taken by another owl or egg.
+            if normalize_owl_name(&self.name).is_empty() {
+                return Err(ErrorKind::EmptyName.into());
             }
+            if !schema.is_owl_name_free(&self.name) {
+                return Err(ErrorKind::OwlNameTaken.into());
+            }
+
+            let config = schema.config();
 
             // Check if user has enough funds for breeding.
-            if user.balance < BREEDING_PRICE {
+            if user.balance < config.breeding_price {
                 return Err(ErrorKind::InsufficientFunds.into());
             }
 
-            // Check last breeding time for each owl.
-            if parents
-                .iter()
-                .any(|p| (ts - p.last_breeding).num_seconds() < BREEDING_TIMEOUT)
-            {
-                return Err(ErrorKind::EarlyBreeding.into());
-            }
-
-            // All conditions are fulfilled, start breeding.
-            let son = schema.make_uniq_owl((father.dna, mother.dna), &self.name, &state_hash);
-            let owls_to_update = vec![son, mother, father];
-            schema.refresh_owls(&user.public_key, owls_to_update, ts);
+            // All conditions are fulfilled, start breeding. DNA of the son is not known
+            // until the egg is hatched with the randomness unavailable at this moment.
+            let egg = Egg {
+                name: self.name.clone(),
+                father_dna: father.owl.dna,
+                mother_dna: mother.owl.dna,
+                owner: user.public_key,
+                laid_at: height.0,
+                bred_at: ts,
+                hatch_at: ts + Duration::seconds(config.hatching_timeout),
+                hatching_height: 0,
+                father_id: self.father_id,
+                mother_id: self.mother_id,
+                inbreeding: schema.inbreeding_coefficient(&self.father_id, &self.mother_id),
+            };
+            schema.lay_egg(&egg_id, egg);
 
-            schema.decrease_user_balance(&user.public_key, BREEDING_PRICE);
+            schema.decrease_user_balance(&user.public_key, config.breeding_price)?;
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::EggLaid, egg_id)
+                    .with_owl(egg_id)
+                    .with_amount(config.breeding_price),
+            );
 
             Ok(())
         }
@@ -433,18 +1773,29 @@ pub mod transactions {
 
     impl Transaction for Issue {
         fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
-            let ts = current_time(context.fork()).unwrap();
+            let ts = current_time(context.fork()).ok_or_else(|| ErrorKind::TimeNotAvailable)?;
 
             let author = context.author();
+            let tx_hash = context.tx_hash();
             let mut schema = CryptoOwlsSchema::new(context.fork());
-            let user = schema.users().get(&author).unwrap();
 
-            if (ts - user.last_fillup).num_seconds() < ISSUE_TIMEOUT {
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+            let config = schema.config();
+
+            if (ts - user.last_fillup).num_seconds() < config.issue_timeout {
                 // Issue timeout is not expired.
                 return Err(ErrorKind::EarlyIssue.into());
             }
 
-            schema.increase_user_balance(&user.public_key, ISSUE_AMOUNT, Some(ts));
+            schema.increase_user_balance(&user.public_key, config.issue_amount, Some(ts))?;
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::FundsIssued, tx_hash).with_amount(config.issue_amount),
+            );
             Ok(())
         }
     }
@@ -452,7 +1803,8 @@ pub mod transactions {
     impl Transaction for CreateAuction {
         fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
             let author = context.author();
-            let ts = current_time(context.fork()).unwrap();
+            let tx_hash = context.tx_hash();
+            let ts = current_time(context.fork()).ok_or_else(|| ErrorKind::TimeNotAvailable)?;
 
             let mut schema = CryptoOwlsSchema::new(context.fork());
             let auction = Auction {
@@ -460,8 +1812,45 @@ pub mod transactions {
                 owl_id: self.owl_id,
                 start_price: self.start_price,
                 duration: self.duration,
+                cancellable_with_bids: self.cancellable_with_bids,
+                reserve_price: 0,
+                buyout_price: self.buyout_price,
+                kind: self.kind,
+                reveal_duration: self.reveal_duration,
+                floor_price: self.floor_price,
+                decay_interval: self.decay_interval,
+                reserve_commitment: self.reserve_commitment,
             };
 
+            // Buy-out price should be above the start price. The seller agrees to sell
+            // the owl for it regardless of the reserve price.
+            // Sealed bids are unknown until the reveal, so they can not reach it.
+            if auction.buyout_price != 0
+                && (auction.kind.is_sealed() || auction.buyout_price <= auction.start_price)
+            {
+                return Err(ErrorKind::InvalidAuctionPrices.into());
+            }
+
+            // Sealed bids and the reserve price need time to be revealed.
+            if auction.has_reveal_phase() && auction.reveal_duration == 0 {
+                return Err(ErrorKind::InvalidAuctionDuration.into());
+            }
+
+            // Dutch auction price decays from the start price to the floor price,
+            // and the first bid covering it wins.
+            if auction.kind == AuctionKind::Dutch {
+                if auction.floor_price > auction.start_price {
+                    return Err(ErrorKind::InvalidFloorPrice.into());
+                }
+                // The reserve and buy-out prices make no sense for the decaying price.
+                if auction.reserve_commitment != Hash::zero() || auction.buyout_price != 0 {
+                    return Err(ErrorKind::WrongAuctionKind.into());
+                }
+                if auction.duration == 0 {
+                    return Err(ErrorKind::InvalidAuctionDuration.into());
+                }
+            }
+
             // Check if the user is registered.
             let user = schema
                 .users()
@@ -479,25 +1868,39 @@ pub mod transactions {
                 return Err(ErrorKind::OwlNotOwned.into());
             }
 
-            // Check if the owl isn't auctioned already.
-            if schema.owl_auction().get(&auction.owl_id).is_some() {
-                return Err(ErrorKind::OwlAlreadyAuctioned.into());
-            }
+            // Check if the owl isn't auctioned or listed already.
+            schema.ensure_owl_not_on_sale(&auction.owl_id)?;
 
             // Establish a new auction.
             let auction_id = schema.auctions().len();
             let owl_id = auction.owl_id;
+            let ends_at = ts + Duration::seconds(self.duration as i64);
             let state = AuctionState {
                 id: auction_id,
                 auction,
                 started_at: ts,
                 bidding_merkle_root: Hash::zero(),
                 closed: false,
+                close_reason: CloseReason::Open,
+                ends_at,
+                phase: AuctionPhase::Bidding,
+                commitments_merkle_root: Hash::zero(),
             };
 
             schema.auctions_mut().push(state);
             schema.owl_auction_mut().put(&owl_id, auction_id);
+            schema.auction_expiry_mut().insert(AuctionExpiry {
+                ends_at,
+                auction_id,
+            });
             schema.user_auctions_mut(&user.public_key).push(auction_id);
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::AuctionCreated, tx_hash)
+                    .with_owl(owl_id)
+                    .with_auction(auction_id)
+                    .with_amount(self.start_price),
+            );
 
             Ok(())
         }
@@ -506,6 +1909,9 @@ pub mod transactions {
     impl Transaction for MakeBid {
         fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
             let author = context.author();
+            let tx_hash = context.tx_hash();
+            let ts = current_time(context.fork()).ok_or_else(|| ErrorKind::TimeNotAvailable)?;
+
             let mut schema = CryptoOwlsSchema::new(context.fork());
 
             // Check if the user is registered.
@@ -520,16 +1926,22 @@ pub mod transactions {
                 .get(self.auction_id)
                 .ok_or_else(|| ErrorKind::AuctionNotFound)?;
 
-            let auction = auction_state.auction;
+            let auction = auction_state.auction.clone();
 
-            // Check if the auction is open.
-            if auction_state.closed {
+            // Check if the auction is open. Auction may be not closed yet
+            // if its time is over in the current block.
+            if auction_state.closed || auction_state.ends_at <= ts {
                 return Err(ErrorKind::AuctionClosed.into());
             }
 
-            // Check if the user has enough funds.
-            if user.balance < self.value {
-                return Err(ErrorKind::InsufficientFunds.into());
+            // Sealed-bid auctions accept commitments only.
+            if auction.kind.is_sealed() {
+                return Err(ErrorKind::WrongAuctionKind.into());
+            }
+
+            // Bids are not accepted once the reserve price is being revealed.
+            if auction_state.phase != AuctionPhase::Bidding {
+                return Err(ErrorKind::WrongAuctionPhase.into());
             }
 
             // Bidding in own auction is prohibited.
@@ -543,27 +1955,72 @@ pub mod transactions {
                 None => auction.start_price,
             };
 
-            // Check if the bid is higher than the min bid.
-            if min_bid >= self.value {
-                return Err(ErrorKind::BidTooLow.into());
+            let (value, settle) = if auction.kind == AuctionKind::Dutch {
+                // The first bid covering the current price buys the owl at this price.
+                let price = auction_state.dutch_price(ts);
+                if self.value < price {
+                    return Err(ErrorKind::BidTooLow.into());
+                }
+                (price, true)
+            } else {
+                // Check if the bid is higher than the min bid.
+                if min_bid >= self.value {
+                    return Err(ErrorKind::BidTooLow.into());
+                }
+
+                // Bids above the buy-out price are charged by the buy-out price.
+                let buyout = auction.buyout_price != 0 && self.value >= auction.buyout_price;
+                if buyout {
+                    (auction.buyout_price, true)
+                } else {
+                    (self.value, false)
+                }
+            };
+
+            // Check if the user has enough funds to pay the charged amount.
+            if user.balance < value {
+                return Err(ErrorKind::InsufficientFunds.into());
             }
 
             // Release balance of the previous bidder if any.
             if let Some(b) = schema.auction_bids(auction_state.id).last() {
-                let prev_bid_user = schema.users().get(&b.public_key).unwrap();
-                schema.release_user_balance(&prev_bid_user.public_key, min_bid);
+                schema.release_user_balance(&b.public_key, min_bid)?;
+                schema.record_event(
+                    &b.public_key,
+                    UserEvent::new(EventKind::Outbid, tx_hash)
+                        .with_owl(auction.owl_id)
+                        .with_auction(auction_state.id)
+                        .with_counterparty(user.public_key)
+                        .with_amount(value),
+                );
             }
 
             // Reserve value in user wallet.
-            schema.reserve_user_balance(&user.public_key, self.value);
+            schema.reserve_user_balance(&user.public_key, value)?;
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::BidPlaced, tx_hash)
+                    .with_owl(auction.owl_id)
+                    .with_auction(auction_state.id)
+                    .with_amount(value),
+            );
 
             // Make a bid.
             let bid = Bid {
                 public_key: author,
-                value: self.value,
+                value,
             };
             schema.auction_bids_mut(self.auction_id).push(bid);
 
+            // Late bid extends the auction to prevent sniping.
+            let extension_window = Duration::seconds(schema.config().auction_extension_window);
+            let ends_at = if !settle && auction_state.ends_at - ts < extension_window {
+                ts + extension_window
+            } else {
+                auction_state.ends_at
+            };
+            schema.reschedule_auction(auction_state.id, auction_state.ends_at, ends_at);
+
             // Refresh the auction state.
             let bidding_merkle_root = schema.auction_bids(self.auction_id).merkle_root();
             schema.auctions_mut().set(
@@ -574,55 +2031,891 @@ pub mod transactions {
                     started_at: auction_state.started_at,
                     bidding_merkle_root,
                     closed: auction_state.closed,
+                    close_reason: auction_state.close_reason,
+                    ends_at,
+                    phase: auction_state.phase,
+                    commitments_merkle_root: auction_state.commitments_merkle_root,
                 },
             );
 
+            // Buy-out (or purchase in the Dutch auction) settles the auction
+            // without waiting for its end.
+            if settle {
+                schema.settle_auction(auction_state.id, &tx_hash)?;
+            }
+
             Ok(())
         }
     }
 
-    /// Helper methods.
-    impl<T> CryptoOwlsSchema<T>
-    where
-        T: AsRef<dyn Snapshot>,
-    {
-        // Method to generate new unique owl
-        pub fn make_uniq_owl(&self, genes: (u32, u32), name: &str, hash_seed: &Hash) -> CryptoOwl {
-            // Hash is a byte array [u8; 32]. To seed random number generator an array
-            // of 32-bit numbers &[u32] is required. So we use `std::io::Cursor` and build
-            // a new u32 number of each 4 bytes.
+    impl Transaction for TransferOwl {
+        fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+            let author = context.author();
+            let tx_hash = context.tx_hash();
+            let mut schema = CryptoOwlsSchema::new(context.fork());
 
-            let hash_seed: &[u8] = hash_seed.as_ref();
-            let mut seed = [0u32; 4];
-            let mut cursor = Cursor::new(hash_seed);
-            for seed in seed.iter_mut().take(4) {
-                *seed = cursor.read_u32::<BigEndian>().unwrap();
-            }
-            let mut rng = IsaacRng::from_seed(&seed);
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
 
-            // Create a unique owl using infinite loop. Call `break` if resulted owl is unique.
-            loop {
-                let mut son_dna = 0u32;
-                // Checking every bit in parent DNAs.
-                for i in 0..32 {
-                    // Step by all `genes` and set them in accordance with parents genes.
-                    let mask = 2u32.pow(i);
-                    let (fg, mg) = (genes.0 & mask, genes.1 & mask);
-                    if fg == mg {
-                        // With a probability of 8/10 the child bits will be equal to parents bits
-                        // in the case if parents bits are equal.
-                        let mut possible_genes = vec![
-                            Weighted {
-                                weight: 8,
-                                item: fg,
-                            },
-                            Weighted {
-                                weight: 2,
-                                item: fg ^ mask,
-                            },
-                        ];
+            // Check if the receiver is registered.
+            let receiver = schema
+                .users()
+                .get(&self.to)
+                .ok_or_else(|| ErrorKind::ReceiverIsNotRegistered)?;
 
-                        let mut choices = WeightedChoice::new(&mut possible_genes);
+            // Giving an owl to yourself is pointless.
+            if user.public_key == receiver.public_key {
+                return Err(ErrorKind::SelfTransfer.into());
+            }
+
+            // Check if the owl (or the egg) exists.
+            let owner = schema
+                .owl_owner(&self.owl_id)
+                .ok_or_else(|| ErrorKind::OwlNotFound)?;
+
+            // Check if the user owns the owl.
+            if owner != user.public_key {
+                return Err(ErrorKind::OwlNotOwned.into());
+            }
+
+            // Owl on sale belongs to the auction or listing until it is closed.
+            schema.ensure_owl_not_on_sale(&self.owl_id)?;
+
+            schema.change_owl_owner(&self.owl_id, &receiver.public_key);
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::OwlGiven, tx_hash)
+                    .with_owl(self.owl_id)
+                    .with_counterparty(receiver.public_key),
+            );
+            schema.record_event(
+                &receiver.public_key,
+                UserEvent::new(EventKind::OwlReceived, tx_hash)
+                    .with_owl(self.owl_id)
+                    .with_counterparty(user.public_key),
+            );
+            Ok(())
+        }
+    }
+
+    impl Transaction for Transfer {
+        fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+            let author = context.author();
+            let tx_hash = context.tx_hash();
+            let mut schema = CryptoOwlsSchema::new(context.fork());
+
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+
+            // Check if the receiver is registered.
+            let receiver = schema
+                .users()
+                .get(&self.to)
+                .ok_or_else(|| ErrorKind::ReceiverIsNotRegistered)?;
+
+            if user.public_key == receiver.public_key {
+                return Err(ErrorKind::SelfTransfer.into());
+            }
+
+            // Reserved funds participate in auctions and can not be transferred.
+            if user.balance < self.amount {
+                return Err(ErrorKind::InsufficientFunds.into());
+            }
+
+            schema.decrease_user_balance(&user.public_key, self.amount)?;
+            schema.increase_user_balance(&receiver.public_key, self.amount, None)?;
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::FundsSent, tx_hash)
+                    .with_counterparty(receiver.public_key)
+                    .with_amount(self.amount),
+            );
+            schema.record_event(
+                &receiver.public_key,
+                UserEvent::new(EventKind::FundsReceived, tx_hash)
+                    .with_counterparty(user.public_key)
+                    .with_amount(self.amount),
+            );
+            Ok(())
+        }
+    }
+
+    impl Transaction for CancelAuction {
+        fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+            let author = context.author();
+            let tx_hash = context.tx_hash();
+            let ts = current_time(context.fork()).ok_or_else(|| ErrorKind::TimeNotAvailable)?;
+
+            let mut schema = CryptoOwlsSchema::new(context.fork());
+
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+
+            // Check if the auction exists.
+            let auction_state = schema
+                .auctions()
+                .get(self.auction_id)
+                .ok_or_else(|| ErrorKind::AuctionNotFound)?;
+
+            // Only the seller can cancel the auction.
+            if auction_state.auction.public_key != user.public_key {
+                return Err(ErrorKind::AccessViolation.into());
+            }
+
+            // Check if the auction is open. Auction may be not closed yet
+            // if its time is over in the current block, but it is finished already.
+            if auction_state.closed || auction_state.ends_at <= ts {
+                return Err(ErrorKind::AuctionClosed.into());
+            }
+
+            // Bidding is over once the bids (or the reserve price) are being revealed.
+            if auction_state.phase != AuctionPhase::Bidding {
+                return Err(ErrorKind::WrongAuctionPhase.into());
+            }
+
+            // Bidders are protected unless the seller allowed cancellation with bids.
+            if !auction_state.auction.cancellable_with_bids
+                && schema.auction_has_bids(&auction_state)
+            {
+                return Err(ErrorKind::AuctionHasBids.into());
+            }
+
+            schema.cancel_auction(auction_state.id, &tx_hash)?;
+            Ok(())
+        }
+    }
+
+    impl Transaction for ListOwl {
+        fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+            let author = context.author();
+            let tx_hash = context.tx_hash();
+            let ts = current_time(context.fork()).ok_or_else(|| ErrorKind::TimeNotAvailable)?;
+
+            let mut schema = CryptoOwlsSchema::new(context.fork());
+
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+
+            // Check if the owl (or the egg) exists.
+            let owner = schema
+                .owl_owner(&self.owl_id)
+                .ok_or_else(|| ErrorKind::OwlNotFound)?;
+
+            // Check if the user owns the owl.
+            if owner != user.public_key {
+                return Err(ErrorKind::OwlNotOwned.into());
+            }
+
+            // Check if the owl isn't auctioned or listed already.
+            schema.ensure_owl_not_on_sale(&self.owl_id)?;
+
+            let listing_id = schema.listings().len();
+            let state = ListingState {
+                id: listing_id,
+                listing: Listing {
+                    public_key: user.public_key,
+                    owl_id: self.owl_id,
+                    price: self.price,
+                },
+                listed_at: ts,
+                closed: false,
+                close_reason: CloseReason::Open,
+            };
+
+            schema.listings_mut().push(state);
+            schema.owl_listing_mut().put(&self.owl_id, listing_id);
+            schema.user_listings_mut(&user.public_key).push(listing_id);
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::OwlListed, tx_hash)
+                    .with_owl(self.owl_id)
+                    .with_amount(self.price),
+            );
+
+            Ok(())
+        }
+    }
+
+    impl Transaction for BuyOwl {
+        fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+            let author = context.author();
+            let tx_hash = context.tx_hash();
+            let mut schema = CryptoOwlsSchema::new(context.fork());
+
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+
+            // Check if the listing exists.
+            let listing_state = schema
+                .listings()
+                .get(self.listing_id)
+                .ok_or_else(|| ErrorKind::ListingNotFound)?;
+
+            // Check if the listing is open.
+            if listing_state.closed {
+                return Err(ErrorKind::ListingClosed.into());
+            }
+
+            let listing = listing_state.listing.clone();
+
+            // Buying own owl is prohibited.
+            if user.public_key == listing.public_key {
+                return Err(ErrorKind::NoSelfBuying.into());
+            }
+
+            // Check if the user has enough funds.
+            if user.balance < listing.price {
+                return Err(ErrorKind::InsufficientFunds.into());
+            }
+
+            schema.decrease_user_balance(&user.public_key, listing.price)?;
+            schema.increase_user_balance(&listing.public_key, listing.price, None)?;
+            schema.change_owl_owner(&listing.owl_id, &user.public_key);
+            schema.close_listing(listing_state, CloseReason::Sold);
+            schema.record_sale(
+                &tx_hash,
+                &listing.owl_id,
+                &listing.public_key,
+                &user.public_key,
+                listing.price,
+            );
+
+            Ok(())
+        }
+    }
+
+    impl Transaction for DelistOwl {
+        fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+            let author = context.author();
+            let tx_hash = context.tx_hash();
+            let mut schema = CryptoOwlsSchema::new(context.fork());
+
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+
+            // Check if the listing exists.
+            let listing_state = schema
+                .listings()
+                .get(self.listing_id)
+                .ok_or_else(|| ErrorKind::ListingNotFound)?;
+
+            // Only the seller can withdraw the owl from sale.
+            if listing_state.listing.public_key != user.public_key {
+                return Err(ErrorKind::AccessViolation.into());
+            }
+
+            // Check if the listing is open.
+            if listing_state.closed {
+                return Err(ErrorKind::ListingClosed.into());
+            }
+
+            let owl_id = listing_state.listing.owl_id;
+            schema.close_listing(listing_state, CloseReason::Cancelled);
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::OwlDelisted, tx_hash).with_owl(owl_id),
+            );
+            Ok(())
+        }
+    }
+
+    impl Transaction for CommitBid {
+        fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+            let author = context.author();
+            let tx_hash = context.tx_hash();
+            let ts = current_time(context.fork()).ok_or_else(|| ErrorKind::TimeNotAvailable)?;
+
+            let mut schema = CryptoOwlsSchema::new(context.fork());
+
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+
+            // Check if the auction exists.
+            let auction_state = schema
+                .auctions()
+                .get(self.auction_id)
+                .ok_or_else(|| ErrorKind::AuctionNotFound)?;
+
+            // Check if the auction is open.
+            if auction_state.closed {
+                return Err(ErrorKind::AuctionClosed.into());
+            }
+
+            // Only sealed-bid auctions accept commitments.
+            if !auction_state.auction.kind.is_sealed() {
+                return Err(ErrorKind::WrongAuctionKind.into());
+            }
+
+            // Commitments are accepted until the commit phase is over.
+            if auction_state.phase != AuctionPhase::Bidding || auction_state.ends_at <= ts {
+                return Err(ErrorKind::WrongAuctionPhase.into());
+            }
+
+            // Bidding in own auction is prohibited.
+            if user.public_key == auction_state.auction.public_key {
+                return Err(ErrorKind::NoSelfBidding.into());
+            }
+
+            // Each participant commits only once.
+            if schema
+                .sealed_bids(auction_state.id)
+                .contains(&user.public_key)
+            {
+                return Err(ErrorKind::BidAlreadyCommitted.into());
+            }
+
+            // Check if the user has enough funds.
+            if user.balance < self.deposit {
+                return Err(ErrorKind::InsufficientFunds.into());
+            }
+
+            // Reserve the deposit in user wallet.
+            schema.reserve_user_balance(&user.public_key, self.deposit)?;
+
+            let sealed_bid = SealedBid {
+                public_key: user.public_key,
+                commitment: self.commitment,
+                deposit: self.deposit,
+                revealed: false,
+                value: 0,
+            };
+            schema
+                .sealed_bids_mut(auction_state.id)
+                .put(&user.public_key, sealed_bid);
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::BidCommitted, tx_hash)
+                    .with_owl(auction_state.auction.owl_id)
+                    .with_auction(auction_state.id)
+                    .with_amount(self.deposit),
+            );
+            schema.refresh_auction_roots(auction_state);
+
+            Ok(())
+        }
+    }
+
+    impl Transaction for OfferStud {
+        fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+            let author = context.author();
+            let tx_hash = context.tx_hash();
+            let ts = current_time(context.fork()).ok_or_else(|| ErrorKind::TimeNotAvailable)?;
+
+            let mut schema = CryptoOwlsSchema::new(context.fork());
+
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+
+            // Check if the owl exists.
+            let owl = schema
+                .owls_state()
+                .get(&self.owl_id)
+                .ok_or_else(|| ErrorKind::OwlNotFound)?;
+
+            // Check if the user owns the owl.
+            if owl.owner != user.public_key {
+                return Err(ErrorKind::OwlNotOwned.into());
+            }
+
+            // Breeding would put the owl on cooldown while it is being sold.
+            schema.ensure_owl_not_on_sale(&self.owl_id)?;
+
+            schema.stud_offers_mut().put(
+                &self.owl_id,
+                StudOffer {
+                    owl_id: self.owl_id,
+                    public_key: user.public_key,
+                    price: self.price,
+                    offered_at: ts,
+                },
+            );
+            schema
+                .user_stud_offers_mut(&user.public_key)
+                .insert(self.owl_id);
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::StudOffered, tx_hash)
+                    .with_owl(self.owl_id)
+                    .with_amount(self.price),
+            );
+
+            Ok(())
+        }
+    }
+
+    impl Transaction for WithdrawStud {
+        fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+            let author = context.author();
+            let tx_hash = context.tx_hash();
+            let mut schema = CryptoOwlsSchema::new(context.fork());
+
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+
+            // Check if the offer exists.
+            let offer = schema
+                .stud_offers()
+                .get(&self.owl_id)
+                .ok_or_else(|| ErrorKind::StudOfferNotFound)?;
+
+            // Only the owner can withdraw the offer.
+            if offer.public_key != user.public_key {
+                return Err(ErrorKind::AccessViolation.into());
+            }
+
+            schema.withdraw_stud_offer(&self.owl_id);
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::StudWithdrawn, tx_hash).with_owl(self.owl_id),
+            );
+            Ok(())
+        }
+    }
+
+    impl Transaction for BreedWithStud {
+        fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+            let author = context.author();
+            let egg_id = context.tx_hash();
+            let ts = current_time(context.fork()).ok_or_else(|| ErrorKind::TimeNotAvailable)?;
+
+            // The egg is laid in the block which is being created now.
+            let height = Schema::new(context.fork()).height().next();
+
+            let mut schema = CryptoOwlsSchema::new(context.fork());
+
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+
+            // Check if the stud is offered.
+            let offer = schema
+                .stud_offers()
+                .get(&self.stud_id)
+                .ok_or_else(|| ErrorKind::StudOfferNotFound)?;
+
+            // Own owls are bred without the offer.
+            if offer.public_key == user.public_key {
+                return Err(ErrorKind::NoSelfBuying.into());
+            }
+
+            // The owner may have changed the price after the transaction was sent.
+            if offer.price != self.fee {
+                return Err(ErrorKind::StudFeeMismatch.into());
+            }
+
+            let father = schema
+                .owls_state()
+                .get(&self.stud_id)
+                .ok_or_else(|| ErrorKind::OwlNotFound)?;
+            let mother = schema
+                .owls_state()
+                .get(&self.mother_id)
+                .ok_or_else(|| ErrorKind::OwlNotFound)?;
+
+            // The offer is only valid while the stud belongs to the user who made it.
+            if father.owner != offer.public_key {
+                return Err(ErrorKind::StudOfferNotFound.into());
+            }
+
+            // Check if user owns the mother.
+            if mother.owner != user.public_key {
+                return Err(ErrorKind::AccessViolation.into());
+            }
+
+            // Breeding would put the stud on cooldown while it is being sold.
+            schema.ensure_owl_not_on_sale(&self.stud_id)?;
+
+            schema.ensure_can_breed(&father, &mother, ts)?;
+
+            // Reject tx if the name is empty or taken by another owl or egg.
+            if normalize_owl_name(&self.name).is_empty() {
+                return Err(ErrorKind::EmptyName.into());
+            }
+            if !schema.is_owl_name_free(&self.name) {
+                return Err(ErrorKind::OwlNameTaken.into());
+            }
+
+            let config = schema.config();
+
+            // Check if user has enough funds for breeding and the stud fee.
+            let price = config
+                .breeding_price
+                .checked_add(offer.price)
+                .ok_or_else(|| ErrorKind::BalanceOverflow)?;
+            if user.balance < price {
+                return Err(ErrorKind::InsufficientFunds.into());
+            }
+
+            // All conditions are fulfilled, start breeding.
+            let egg = Egg {
+                name: self.name.clone(),
+                father_dna: father.owl.dna,
+                mother_dna: mother.owl.dna,
+                owner: user.public_key,
+                laid_at: height.0,
+                bred_at: ts,
+                hatch_at: ts + Duration::seconds(config.hatching_timeout),
+                hatching_height: 0,
+                father_id: self.stud_id,
+                mother_id: self.mother_id,
+                inbreeding: schema.inbreeding_coefficient(&self.stud_id, &self.mother_id),
+            };
+            schema.lay_egg(&egg_id, egg);
+
+            schema.increase_user_balance(&offer.public_key, offer.price, None)?;
+            schema.decrease_user_balance(&user.public_key, price)?;
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::EggLaid, egg_id)
+                    .with_owl(egg_id)
+                    .with_amount(config.breeding_price),
+            );
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::StudFeePaid, egg_id)
+                    .with_owl(self.stud_id)
+                    .with_counterparty(offer.public_key)
+                    .with_amount(offer.price),
+            );
+            schema.record_event(
+                &offer.public_key,
+                UserEvent::new(EventKind::StudFeeReceived, egg_id)
+                    .with_owl(self.stud_id)
+                    .with_counterparty(user.public_key)
+                    .with_amount(offer.price),
+            );
+
+            Ok(())
+        }
+    }
+
+    impl Transaction for RenameOwl {
+        fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+            let author = context.author();
+            let tx_hash = context.tx_hash();
+            let mut schema = CryptoOwlsSchema::new(context.fork());
+
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+
+            // Eggs are named when hatched.
+            let mut owl_state = schema
+                .owls_state()
+                .get(&self.owl_id)
+                .ok_or_else(|| ErrorKind::OwlNotFound)?;
+
+            // Only the owner can rename the owl.
+            if owl_state.owner != user.public_key {
+                return Err(ErrorKind::AccessViolation.into());
+            }
+
+            // Reject tx if the name is empty or taken by another owl or egg. The owl may
+            // keep its own name, e.g. to change the case.
+            if normalize_owl_name(&self.name).is_empty() {
+                return Err(ErrorKind::EmptyName.into());
+            }
+            let name_key = owl_name_key(&self.name);
+            match schema.owl_names().get(&name_key) {
+                Some(id) if id != self.owl_id => return Err(ErrorKind::OwlNameTaken.into()),
+                _ => {}
+            }
+
+            let old_key = owl_name_key(&owl_state.name);
+            if schema.owl_names().get(&old_key) == Some(self.owl_id) {
+                schema.owl_names_mut().remove(&old_key);
+            }
+            schema.owl_names_mut().put(&name_key, self.owl_id);
+
+            owl_state.name = self.name.clone();
+            schema.owls_state_mut().put(&self.owl_id, owl_state);
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::OwlRenamed, tx_hash).with_owl(self.owl_id),
+            );
+            Ok(())
+        }
+    }
+
+    impl Transaction for UpdateProfile {
+        fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+            let author = context.author();
+            let tx_hash = context.tx_hash();
+            let mut schema = CryptoOwlsSchema::new(context.fork());
+
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+
+            if self.name.trim().is_empty() {
+                return Err(ErrorKind::EmptyName.into());
+            }
+
+            schema.users_mut().put(
+                &author,
+                User {
+                    public_key: user.public_key,
+                    name: self.name.clone(),
+                    balance: user.balance,
+                    reserved: user.reserved,
+                    last_fillup: user.last_fillup,
+                },
+            );
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::ProfileUpdated, tx_hash),
+            );
+            Ok(())
+        }
+    }
+
+    impl Transaction for RevealBid {
+        fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+            let author = context.author();
+            let tx_hash = context.tx_hash();
+            let ts = current_time(context.fork()).ok_or_else(|| ErrorKind::TimeNotAvailable)?;
+
+            let mut schema = CryptoOwlsSchema::new(context.fork());
+
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+
+            // Check if the auction exists.
+            let auction_state = schema
+                .auctions()
+                .get(self.auction_id)
+                .ok_or_else(|| ErrorKind::AuctionNotFound)?;
+
+            // Check if the auction is open.
+            if auction_state.closed {
+                return Err(ErrorKind::AuctionClosed.into());
+            }
+
+            // Only sealed-bid auctions accept reveals.
+            if !auction_state.auction.kind.is_sealed() {
+                return Err(ErrorKind::WrongAuctionKind.into());
+            }
+
+            // Bids are revealed after the commit phase is over.
+            if auction_state.phase != AuctionPhase::Reveal || auction_state.ends_at <= ts {
+                return Err(ErrorKind::WrongAuctionPhase.into());
+            }
+
+            // Check if the bid was committed.
+            let sealed_bid = schema
+                .sealed_bids(auction_state.id)
+                .get(&user.public_key)
+                .ok_or_else(|| ErrorKind::BidNotCommitted)?;
+
+            if sealed_bid.revealed {
+                return Err(ErrorKind::BidAlreadyRevealed.into());
+            }
+
+            // Check if the revealed bid matches the commitment.
+            if sealed_bid_commitment(self.value, &self.salt, &user.public_key)
+                != sealed_bid.commitment
+            {
+                return Err(ErrorKind::CommitmentMismatch.into());
+            }
+
+            // Revealed bid should be covered by the deposit.
+            if self.value > sealed_bid.deposit {
+                return Err(ErrorKind::BidExceedsDeposit.into());
+            }
+
+            // Check if the bid is not lower than the start price.
+            if self.value < auction_state.auction.start_price {
+                return Err(ErrorKind::BidTooLow.into());
+            }
+
+            schema.sealed_bids_mut(auction_state.id).put(
+                &user.public_key,
+                SealedBid {
+                    public_key: sealed_bid.public_key,
+                    commitment: sealed_bid.commitment,
+                    deposit: sealed_bid.deposit,
+                    revealed: true,
+                    value: self.value,
+                },
+            );
+
+            // Revealed bids are public, so they are added to the bids history.
+            schema.auction_bids_mut(auction_state.id).push(Bid {
+                public_key: user.public_key,
+                value: self.value,
+            });
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::BidPlaced, tx_hash)
+                    .with_owl(auction_state.auction.owl_id)
+                    .with_auction(auction_state.id)
+                    .with_amount(self.value),
+            );
+            schema.refresh_auction_roots(auction_state);
+
+            Ok(())
+        }
+    }
+
+    impl Transaction for RevealReserve {
+        fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+            let author = context.author();
+            let tx_hash = context.tx_hash();
+            let ts = current_time(context.fork()).ok_or_else(|| ErrorKind::TimeNotAvailable)?;
+
+            let mut schema = CryptoOwlsSchema::new(context.fork());
+
+            // Check if the user is registered.
+            let user = schema
+                .users()
+                .get(&author)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+
+            // Check if the auction exists.
+            let auction_state = schema
+                .auctions()
+                .get(self.auction_id)
+                .ok_or_else(|| ErrorKind::AuctionNotFound)?;
+
+            // Only the seller can reveal the reserve price.
+            if auction_state.auction.public_key != user.public_key {
+                return Err(ErrorKind::AccessViolation.into());
+            }
+
+            // Check if the auction is open.
+            if auction_state.closed {
+                return Err(ErrorKind::AuctionClosed.into());
+            }
+
+            if auction_state.auction.reserve_commitment == Hash::zero() {
+                return Err(ErrorKind::ReserveNotCommitted.into());
+            }
+
+            // Reserve price is revealed after bidding is over, so bidders do not know it.
+            if auction_state.phase != AuctionPhase::Reveal || auction_state.ends_at <= ts {
+                return Err(ErrorKind::WrongAuctionPhase.into());
+            }
+
+            // Check if the revealed reserve price matches the commitment.
+            if reserve_price_commitment(self.reserve_price, &self.salt, &user.public_key)
+                != auction_state.auction.reserve_commitment
+            {
+                return Err(ErrorKind::CommitmentMismatch.into());
+            }
+
+            let mut auction = auction_state.auction.clone();
+            auction.reserve_price = self.reserve_price;
+            schema.record_event(
+                &user.public_key,
+                UserEvent::new(EventKind::ReserveRevealed, tx_hash)
+                    .with_owl(auction.owl_id)
+                    .with_auction(auction_state.id)
+                    .with_amount(self.reserve_price),
+            );
+            schema.auctions_mut().set(
+                auction_state.id,
+                AuctionState {
+                    id: auction_state.id,
+                    auction,
+                    started_at: auction_state.started_at,
+                    bidding_merkle_root: auction_state.bidding_merkle_root,
+                    closed: auction_state.closed,
+                    close_reason: auction_state.close_reason,
+                    ends_at: auction_state.ends_at,
+                    phase: auction_state.phase,
+                    commitments_merkle_root: auction_state.commitments_merkle_root,
+                },
+            );
+
+            Ok(())
+        }
+    }
+
+    /// Helper methods.
+    impl<T> CryptoOwlsSchema<T>
+    where
+        T: AsRef<dyn Snapshot>,
+    {
+        // Method to generate new unique owl. `mutation_rate` is the probability (per mille)
+        // of the mutation of the gene which is the same in both parents.
+        pub fn make_uniq_owl(
+            &self,
+            genes: (u32, u32),
+            name: &str,
+            hash_seed: &Hash,
+            mutation_rate: u32,
+        ) -> CryptoOwl {
+            // Hash is a byte array [u8; 32]. To seed random number generator an array
+            // of 32-bit numbers &[u32] is required. So we use `std::io::Cursor` and build
+            // a new u32 number of each 4 bytes.
+
+            let hash_seed: &[u8] = hash_seed.as_ref();
+            let mut seed = [0u32; 4];
+            let mut cursor = Cursor::new(hash_seed);
+            for seed in seed.iter_mut().take(4) {
+                *seed = cursor.read_u32::<BigEndian>().unwrap();
+            }
+            let mut rng = IsaacRng::from_seed(&seed);
+
+            // Create a unique owl using infinite loop. Call `break` if resulted owl is unique.
+            loop {
+                let mut son_dna = 0u32;
+                // Checking every bit in parent DNAs.
+                for i in 0..32 {
+                    // Step by all `genes` and set them in accordance with parents genes.
+                    let mask = 2u32.pow(i);
+                    let (fg, mg) = (genes.0 & mask, genes.1 & mask);
+                    if fg == mg {
+                        // With a probability of `1 - mutation_rate` the child bits will be equal
+                        // to parents bits in the case if parents bits are equal.
+                        let mut possible_genes = vec![
+                            Weighted {
+                                weight: 1000 - mutation_rate,
+                                item: fg,
+                            },
+                            Weighted {
+                                weight: mutation_rate,
+                                item: fg ^ mask,
+                            },
+                        ];
+
+                        let mut choices = WeightedChoice::new(&mut possible_genes);
                         son_dna |= choices.sample(&mut rng);
                     } else if rng.gen() {
                         // If bits are different, the resulting bit will be selected
@@ -643,11 +2936,170 @@ pub mod transactions {
                 }
             }
         }
+
+        /// Checks if the owls can be bred with each other at the given time.
+        pub fn ensure_can_breed(
+            &self,
+            father: &CryptoOwlState,
+            mother: &CryptoOwlState,
+            ts: DateTime<Utc>,
+        ) -> Result<(), ErrorKind> {
+            // Can not use the same owl as mother and father at the same time.
+            if father.owl == mother.owl {
+                return Err(ErrorKind::SelfBreeding);
+            }
+
+            let config = self.config();
+
+            // Parents can not be bred with their children, siblings with each other.
+            if config.forbid_inbreeding
+                && self.are_close_relatives(&father.owl.hash(), &mother.owl.hash())
+            {
+                return Err(ErrorKind::Inbreeding);
+            }
+
+            // Check last breeding time for each owl.
+            if [father, mother]
+                .iter()
+                .any(|p| (ts - p.last_breeding).num_seconds() < config.breeding_timeout)
+            {
+                return Err(ErrorKind::EarlyBreeding);
+            }
+            Ok(())
+        }
+
+        /// Returns the known parents of the owl.
+        fn owl_parents(&self, owl_id: &Hash) -> Vec<Hash> {
+            self.owls_metadata()
+                .get(owl_id)
+                .map(|metadata| vec![metadata.father_id, metadata.mother_id])
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|parent_id| *parent_id != Hash::zero())
+                .collect()
+        }
+
+        /// Checks if one of the owls is a parent of another or the owls have a common parent.
+        pub fn are_close_relatives(&self, first_id: &Hash, second_id: &Hash) -> bool {
+            let first_parents = self.owl_parents(first_id);
+            let second_parents = self.owl_parents(second_id);
+            first_parents.contains(second_id)
+                || second_parents.contains(first_id)
+                || first_parents.iter().any(|p| second_parents.contains(p))
+        }
+
+        /// Computes the inbreeding coefficient (per mille) of the child of the given owls.
+        /// Wright's formula is used: every pair of paths from the parents to a common
+        /// ancestor adds `1/2^(n1 + n2 + 1)`, where `n1` and `n2` are the lengths of the paths.
+        /// Paths which meet before the ancestor are not counted: the relationship through
+        /// the ancestors of the owl where they meet is already counted for this owl.
+        /// Only `INBREEDING_DEPTH` generations are visited, and the inbreeding
+        /// of the common ancestors is ignored.
+        pub fn inbreeding_coefficient(&self, father_id: &Hash, mother_id: &Hash) -> u32 {
+            // Every path from the owl to its ancestors, the owl itself is the path
+            // of zero length. Paths are listed from the owl to the ancestor.
+            let paths = |owl_id: &Hash| {
+                let mut paths = vec![vec![*owl_id]];
+                let mut generation = vec![vec![*owl_id]];
+                for _ in 0..INBREEDING_DEPTH {
+                    generation = generation
+                        .iter()
+                        .flat_map(|path| {
+                            self.owl_parents(path.last().unwrap()).into_iter().map(
+                                move |parent_id| {
+                                    let mut path = path.clone();
+                                    path.push(parent_id);
+                                    path
+                                },
+                            )
+                        })
+                        .collect();
+                    paths.extend(generation.iter().cloned());
+                }
+                paths
+            };
+
+            // Contributions are counted in units of `1/2^(2 * INBREEDING_DEPTH + 1)`.
+            let mother_paths = paths(mother_id);
+            let mut coefficient = 0u64;
+            for father_path in paths(father_id) {
+                let (ancestor, father_path) = father_path.split_last().unwrap();
+                let n1 = father_path.len() as u64;
+                for mother_path in &mother_paths {
+                    let (id, mother_path) = mother_path.split_last().unwrap();
+                    if id != ancestor || father_path.iter().any(|id| mother_path.contains(id)) {
+                        continue;
+                    }
+                    let n2 = mother_path.len() as u64;
+                    coefficient += 1 << (2 * INBREEDING_DEPTH - n1 - n2);
+                }
+            }
+            cmp::min(1000, (coefficient * 1000) >> (2 * INBREEDING_DEPTH + 1)) as u32
+        }
+
+        /// Checks if anyone has made a bid (or committed to a sealed bid) in the auction.
+        pub fn auction_has_bids(&self, auction_state: &AuctionState) -> bool {
+            if auction_state.auction.kind.is_sealed() {
+                self.sealed_bids(auction_state.id).keys().next().is_some()
+            } else {
+                !self.auction_bids(auction_state.id).is_empty()
+            }
+        }
+
+        /// Returns the owner of the owl or of the egg with the given identifier.
+        pub fn owl_owner(&self, owl_id: &Hash) -> Option<PublicKey> {
+            self.owls_state()
+                .get(owl_id)
+                .map(|owl_state| owl_state.owner)
+                .or_else(|| self.eggs().get(owl_id).map(|egg| egg.owner))
+        }
+
+        /// Checks if the name is not taken by another owl or egg.
+        pub fn is_owl_name_free(&self, name: &str) -> bool {
+            !self.owl_names().contains(&owl_name_key(name))
+        }
+
+        /// Returns the name if it is free. Otherwise, returns the name with the smallest
+        /// number which makes it free, e.g. "Alice's Adam 2".
+        pub fn unique_owl_name(&self, name: &str) -> String {
+            if self.is_owl_name_free(name) {
+                return name.to_owned();
+            }
+            (2..)
+                .map(|n| format!("{} {}", name, n))
+                .find(|name| self.is_owl_name_free(name))
+                .unwrap()
+        }
+
+        /// Statistics of owl traits. If the statistics are not stored yet (e.g., the owls
+        /// were born before the statistics were introduced), they are collected from `owls_state`.
+        pub fn owl_trait_stats(&self) -> TraitStats {
+            self.trait_stats().get().unwrap_or_else(|| {
+                let mut stats = TraitStats::new();
+                for owl_state in self.owls_state().values() {
+                    stats.add(&Traits::from(&owl_state.owl));
+                }
+                stats
+            })
+        }
+
+        /// Checks that the owl is neither auctioned nor listed for sale.
+        pub fn ensure_owl_not_on_sale(&self, owl_id: &Hash) -> Result<(), ErrorKind> {
+            if self.owl_auction().contains(owl_id) {
+                return Err(ErrorKind::OwlAlreadyAuctioned);
+            }
+            if self.owl_listing().contains(owl_id) {
+                return Err(ErrorKind::OwlAlreadyListed);
+            }
+            Ok(())
+        }
     }
 
     /// Mutable helper methods.
     impl<'a> CryptoOwlsSchema<&'a mut Fork> {
         /// Helper method to update owl state after breeding or creating.
+        /// Names of newborn owls are registered and their traits are added
+        /// to the trait statistics.
         pub fn refresh_owls(
             &mut self,
             owner_key: &PublicKey,
@@ -655,6 +3107,17 @@ pub mod transactions {
             last_breeding: DateTime<Utc>,
         ) {
             for owl in owls {
+                let name = match self.owls_state().get(&owl.hash()) {
+                    Some(owl_state) => owl_state.name,
+                    None => {
+                        self.owl_names_mut()
+                            .put(&owl_name_key(&owl.name), owl.hash());
+                        let mut stats = self.owl_trait_stats();
+                        stats.add(&Traits::from(&owl));
+                        self.trait_stats_mut().set(stats);
+                        owl.name.clone()
+                    }
+                };
                 self.user_owls_mut(owner_key).insert(owl.hash());
                 self.owls_state_mut().put(
                     &owl.hash(),
@@ -662,138 +3125,746 @@ pub mod transactions {
                         owl,
                         owner: *owner_key,
                         last_breeding,
+                        name,
+                    },
+                );
+            }
+        }
+
+        /// Helper method to lay the egg. Parents stay with their owners,
+        /// but they can not be bred again until the breeding timeout expires.
+        /// The name of the egg is reserved for the owl hatched from it.
+        pub fn lay_egg(&mut self, egg_id: &Hash, egg: Egg) {
+            for parent_id in &[egg.father_id, egg.mother_id] {
+                let parent = self.owls_state().get(parent_id).unwrap();
+                self.refresh_owls(&parent.owner, vec![parent.owl], egg.bred_at);
+            }
+            self.owl_names_mut().put(&owl_name_key(&egg.name), *egg_id);
+            self.user_eggs_mut(&egg.owner).insert(*egg_id);
+            self.incubated_eggs_mut().insert(EggIncubation {
+                hatch_at: egg.hatch_at,
+                egg_id: *egg_id,
+            });
+            self.eggs_mut().put(egg_id, egg);
+        }
+
+        /// Migrates the data stored by earlier versions of the service to `SCHEMA_VERSION`.
+        ///
+        /// - Version 1. The current name of the owl is stored in `CryptoOwlState`, so that
+        ///   the owl keeps its identifier (and `owls_state` key) when it is renamed.
+        ///   Owls born before the name registry are registered under their names,
+        ///   with a number appended if the name is already taken.
+        /// - Version 2. Auctions are closed through the `auction_expiry` index.
+        ///   Auction records written before the auction end time (and the later fields)
+        ///   were stored are completed, and the open auctions are added to the index.
+        /// - Version 3. Eggs are hatched through the `incubated_eggs` and `hatching_eggs`
+        ///   indexes. Eggs laid earlier are added to the index matching their state.
+        /// - Version 4. User histories are authenticated through `user_history_roots`.
+        ///   The roots of the histories recorded earlier are added to the table.
+        ///
+        /// The `owls_state` keys are hashes of the `CryptoOwl` records, which are never
+        /// changed by the migration (the current owl name is kept in `CryptoOwlState`),
+        /// so the keys stay valid and are not rewritten.
+        ///
+        /// Does nothing if the data is already up to date.
+        pub fn migrate(&mut self) {
+            let version = self.schema_version().get().unwrap_or(0);
+            if version == SCHEMA_VERSION {
+                return;
+            }
+            if version < 1 {
+                let owls: Vec<_> = self
+                    .owls_state()
+                    .iter()
+                    .filter(|(_, owl_state)| owl_state.name.is_empty())
+                    .collect();
+                for (owl_id, mut owl_state) in owls {
+                    let name_key = owl_name_key(&owl_state.owl.name);
+                    owl_state.name = if self.owl_names().get(&name_key) == Some(owl_id) {
+                        owl_state.owl.name.clone()
+                    } else {
+                        let name = self.unique_owl_name(&owl_state.owl.name);
+                        self.owl_names_mut().put(&owl_name_key(&name), owl_id);
+                        name
+                    };
+                    self.owls_state_mut().put(&owl_id, owl_state);
+                }
+            }
+            if version < 2 {
+                let auctions: Vec<_> = self.raw_auctions().iter().collect();
+                for bytes in auctions {
+                    let mut auction_state =
+                        AuctionState::from_legacy_bytes(&bytes).expect("Invalid auction record.");
+                    // The end time, the phase and the close reason were not stored.
+                    if auction_state.ends_at < auction_state.started_at {
+                        let duration = Duration::seconds(auction_state.auction.duration as i64);
+                        auction_state.ends_at = auction_state.started_at + duration;
+                        if auction_state.closed {
+                            auction_state.phase = AuctionPhase::Closed;
+                            auction_state.close_reason =
+                                if self.auction_bids(auction_state.id).is_empty() {
+                                    CloseReason::Expired
+                                } else {
+                                    CloseReason::Sold
+                                };
+                        }
+                    }
+                    self.auctions_mut().set(auction_state.id, auction_state);
+                }
+
+                let open_auctions: Vec<_> = self.owl_auction().values().collect();
+                for auction_id in open_auctions {
+                    let auction_state = self.auctions().get(auction_id).unwrap();
+                    self.auction_expiry_mut().insert(AuctionExpiry {
+                        ends_at: auction_state.ends_at,
+                        auction_id,
+                    });
+                }
+            }
+            if version < 3 {
+                let eggs: Vec<_> = self.eggs().iter().collect();
+                for (egg_id, egg) in eggs {
+                    if egg.hatching_height == 0 {
+                        self.incubated_eggs_mut().insert(EggIncubation {
+                            hatch_at: egg.hatch_at,
+                            egg_id,
+                        });
+                    } else {
+                        self.hatching_eggs_mut().insert(EggHatching {
+                            hatching_height: egg.hatching_height,
+                            egg_id,
+                        });
+                    }
+                }
+            }
+            if version < 4 {
+                let users: Vec<_> = self.users().keys().collect();
+                for public_key in users {
+                    let history = self.user_history(&public_key);
+                    if !history.is_empty() {
+                        let history_root = history.merkle_root();
+                        self.user_history_roots_mut().put(&public_key, history_root);
+                    }
+                }
+            }
+            self.schema_version_mut().set(SCHEMA_VERSION);
+        }
+
+        /// Helper method to append the event to the user history.
+        /// The event is dated by the height of the block which is being created now.
+        pub fn record_event(&mut self, public_key: &PublicKey, mut event: UserEvent) {
+            event.height = Schema::new(&*self.view).height().next().0;
+            let mut history = self.user_history_mut(public_key);
+            history.push(event);
+            let history_root = history.merkle_root();
+            self.user_history_roots_mut().put(public_key, history_root);
+        }
+
+        /// Helper method to record the sale of the owl (or the egg) in the histories
+        /// of the seller and the buyer.
+        pub fn record_sale(
+            &mut self,
+            tx_hash: &Hash,
+            owl_id: &Hash,
+            seller: &PublicKey,
+            buyer: &PublicKey,
+            price: u64,
+        ) {
+            self.record_event(
+                seller,
+                UserEvent::new(EventKind::OwlSold, *tx_hash)
+                    .with_owl(*owl_id)
+                    .with_counterparty(*buyer)
+                    .with_amount(price),
+            );
+            self.record_event(
+                buyer,
+                UserEvent::new(EventKind::OwlBought, *tx_hash)
+                    .with_owl(*owl_id)
+                    .with_counterparty(*seller)
+                    .with_amount(price),
+            );
+        }
+
+        /// Helper method to withdraw the stud offer of the owl if any.
+        pub fn withdraw_stud_offer(&mut self, owl_id: &Hash) {
+            if let Some(offer) = self.stud_offers().get(owl_id) {
+                self.user_stud_offers_mut(&offer.public_key).remove(owl_id);
+                self.stud_offers_mut().remove(owl_id);
+            }
+        }
+
+        /// Helper method to mark the egg as ready to hatch. The egg is hatched
+        /// with the randomness of the block at the given height.
+        pub fn start_hatching(&mut self, egg_id: &Hash, hatching_height: Height) {
+            let egg = self.eggs().get(egg_id).expect("Egg should be exist.");
+            self.incubated_eggs_mut().remove(&EggIncubation {
+                hatch_at: egg.hatch_at,
+                egg_id: *egg_id,
+            });
+            self.hatching_eggs_mut().insert(EggHatching {
+                hatching_height: hatching_height.0,
+                egg_id: *egg_id,
+            });
+            self.eggs_mut().put(
+                egg_id,
+                Egg {
+                    name: egg.name,
+                    father_dna: egg.father_dna,
+                    mother_dna: egg.mother_dna,
+                    owner: egg.owner,
+                    laid_at: egg.laid_at,
+                    bred_at: egg.bred_at,
+                    hatch_at: egg.hatch_at,
+                    hatching_height: hatching_height.0,
+                    father_id: egg.father_id,
+                    mother_id: egg.mother_id,
+                    inbreeding: egg.inbreeding,
+                },
+            );
+        }
+
+        /// Helper method to record parents of the newborn owl.
+        pub fn record_birth(
+            &mut self,
+            owl_id: &Hash,
+            father_id: &Hash,
+            mother_id: &Hash,
+            inbreeding: u32,
+            born_at: DateTime<Utc>,
+        ) {
+            let mut generation = 0;
+            for parent_id in &[*father_id, *mother_id] {
+                if *parent_id == Hash::zero() {
+                    continue;
+                }
+                let parent_generation = self
+                    .owls_metadata()
+                    .get(parent_id)
+                    .map_or(0, |parent| parent.generation);
+                generation = cmp::max(generation, parent_generation + 1);
+                self.owl_children_mut(parent_id).insert(*owl_id);
+            }
+
+            self.owls_metadata_mut().put(
+                owl_id,
+                OwlMetadata {
+                    father_id: *father_id,
+                    mother_id: *mother_id,
+                    generation,
+                    born_at,
+                    inbreeding,
+                },
+            );
+        }
+
+        /// Helper method to hatch the egg. The seed from the randomness source
+        /// is combined with the egg identifier, so eggs from the same block differ.
+        pub fn hatch_egg(&mut self, egg_id: &Hash, seed: &Hash, born_at: DateTime<Utc>) {
+            let egg = self.eggs().get(egg_id).expect("Egg should be exist.");
+            let seed = crypto::hash(&[seed.as_ref(), egg_id.as_ref()].concat());
+            // Inbreeding raises the mutation rate.
+            let extra_mutation_rate = u64::from(self.config().inbreeding_mutation_rate)
+                * u64::from(egg.inbreeding)
+                / 1000;
+            let mutation_rate =
+                cmp::min(500, u64::from(MUTATION_RATE) + extra_mutation_rate) as u32;
+            let owl = self.make_uniq_owl(
+                (egg.father_dna, egg.mother_dna),
+                &egg.name,
+                &seed,
+                mutation_rate,
+            );
+            let owl_id = owl.hash();
+            self.record_birth(
+                &owl_id,
+                &egg.father_id,
+                &egg.mother_id,
+                egg.inbreeding,
+                born_at,
+            );
+            self.refresh_owls(&egg.owner, vec![owl], egg.bred_at);
+            self.user_eggs_mut(&egg.owner).remove(egg_id);
+            self.hatching_eggs_mut().remove(&EggHatching {
+                hatching_height: egg.hatching_height,
+                egg_id: *egg_id,
+            });
+            self.eggs_mut().remove(egg_id);
+            self.record_event(
+                &egg.owner,
+                UserEvent::new(EventKind::OwlBorn, Hash::zero()).with_owl(owl_id),
+            );
+
+            // The listed egg stays on sale as the hatched owl.
+            if let Some(listing_id) = self.owl_listing().get(egg_id) {
+                let listing_state = self.listings().get(listing_id).unwrap();
+                self.owl_listing_mut().remove(egg_id);
+                self.owl_listing_mut().put(&owl_id, listing_id);
+                self.listings_mut().set(
+                    listing_id,
+                    ListingState {
+                        id: listing_state.id,
+                        listing: Listing {
+                            public_key: listing_state.listing.public_key,
+                            owl_id,
+                            price: listing_state.listing.price,
+                        },
+                        listed_at: listing_state.listed_at,
+                        closed: listing_state.closed,
+                        close_reason: listing_state.close_reason,
+                    },
+                );
+            }
+        }
+
+        /// Helper method to pass the owl (or the egg) to a new owner.
+        pub fn change_owl_owner(&mut self, owl_id: &Hash, new_owner: &PublicKey) {
+            if let Some(egg) = self.eggs().get(owl_id) {
+                self.user_eggs_mut(&egg.owner).remove(owl_id);
+                self.user_eggs_mut(new_owner).insert(*owl_id);
+                self.eggs_mut().put(
+                    owl_id,
+                    Egg {
+                        name: egg.name,
+                        father_dna: egg.father_dna,
+                        mother_dna: egg.mother_dna,
+                        owner: *new_owner,
+                        laid_at: egg.laid_at,
+                        bred_at: egg.bred_at,
+                        hatch_at: egg.hatch_at,
+                        hatching_height: egg.hatching_height,
+                        father_id: egg.father_id,
+                        mother_id: egg.mother_id,
+                        inbreeding: egg.inbreeding,
                     },
                 );
+                return;
             }
+
+            let owl_state = self.owls_state().get(owl_id).expect("Owl should be exist.");
+
+            // The offer was made by the previous owner.
+            self.withdraw_stud_offer(owl_id);
+
+            // Remove possession from the previous owner.
+            self.user_owls_mut(&owl_state.owner).remove(owl_id);
+
+            // Pass it to the new owner.
+            self.user_owls_mut(new_owner).insert(*owl_id);
+
+            // Change owl owner.
+            self.owls_state_mut().put(
+                owl_id,
+                CryptoOwlState {
+                    owl: owl_state.owl,
+                    owner: *new_owner,
+                    last_breeding: owl_state.last_breeding,
+                    name: owl_state.name,
+                },
+            );
+        }
+
+        /// Helper method to increase user balance. Total funds of the user
+        /// (`balance + reserved`) should fit into `u64`, so that moving funds
+        /// between the balance and the reserve never overflows.
+        pub fn increase_user_balance(
+            &mut self,
+            user_id: &PublicKey,
+            amount: u64,
+            last_fillup: Option<DateTime<Utc>>,
+        ) -> Result<(), ErrorKind> {
+            let user = self
+                .users()
+                .get(user_id)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+            user.balance
+                .checked_add(user.reserved)
+                .and_then(|total| total.checked_add(amount))
+                .ok_or_else(|| ErrorKind::BalanceOverflow)?;
+
+            let balance = user.balance + amount;
+            let reserved = user.reserved;
+            let last_fillup = last_fillup.unwrap_or(user.last_fillup);
+            self.set_user_funds(user, balance, reserved, last_fillup);
+            Ok(())
+        }
+
+        /// Helper method to decrease user balance.
+        pub fn decrease_user_balance(
+            &mut self,
+            user_id: &PublicKey,
+            amount: u64,
+        ) -> Result<(), ErrorKind> {
+            let user = self
+                .users()
+                .get(user_id)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+            let balance = user
+                .balance
+                .checked_sub(amount)
+                .ok_or_else(|| ErrorKind::InsufficientFunds)?;
+
+            let (reserved, last_fillup) = (user.reserved, user.last_fillup);
+            self.set_user_funds(user, balance, reserved, last_fillup);
+            Ok(())
+        }
+
+        /// Helper method to move funds from user balance to the reserve.
+        pub fn reserve_user_balance(
+            &mut self,
+            user_id: &PublicKey,
+            amount: u64,
+        ) -> Result<(), ErrorKind> {
+            let user = self
+                .users()
+                .get(user_id)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+            let balance = user
+                .balance
+                .checked_sub(amount)
+                .ok_or_else(|| ErrorKind::InsufficientFunds)?;
+            let reserved = user
+                .reserved
+                .checked_add(amount)
+                .ok_or_else(|| ErrorKind::BalanceOverflow)?;
+
+            let last_fillup = user.last_fillup;
+            self.set_user_funds(user, balance, reserved, last_fillup);
+            Ok(())
         }
 
-        /// Helper method to increase user balance.
-        pub fn increase_user_balance(
+        /// Helper method to move funds from the user reserve back to the balance.
+        pub fn release_user_balance(
+            &mut self,
+            user_id: &PublicKey,
+            amount: u64,
+        ) -> Result<(), ErrorKind> {
+            let user = self
+                .users()
+                .get(user_id)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+            let reserved = user
+                .reserved
+                .checked_sub(amount)
+                .ok_or_else(|| ErrorKind::InsufficientFunds)?;
+            let balance = user
+                .balance
+                .checked_add(amount)
+                .ok_or_else(|| ErrorKind::BalanceOverflow)?;
+
+            let last_fillup = user.last_fillup;
+            self.set_user_funds(user, balance, reserved, last_fillup);
+            Ok(())
+        }
+
+        /// Helper method to withdraw the won bid from the user reserve.
+        pub fn confirm_user_bid(
             &mut self,
             user_id: &PublicKey,
+            bid_value: u64,
+        ) -> Result<(), ErrorKind> {
+            let user = self
+                .users()
+                .get(user_id)
+                .ok_or_else(|| ErrorKind::UserIsNotRegistered)?;
+            let reserved = user
+                .reserved
+                .checked_sub(bid_value)
+                .ok_or_else(|| ErrorKind::InsufficientFunds)?;
+
+            let (balance, last_fillup) = (user.balance, user.last_fillup);
+            self.set_user_funds(user, balance, reserved, last_fillup);
+            Ok(())
+        }
+
+        /// Helper method to store the user with the updated funds.
+        fn set_user_funds(
+            &mut self,
+            user: User,
             balance: u64,
-            last_fillup: Option<DateTime<Utc>>,
+            reserved: u64,
+            last_fillup: DateTime<Utc>,
         ) {
-            let user = self.users().get(user_id).expect("User should be exist.");
-            let last_fillup = last_fillup.unwrap_or_else(|| user.last_fillup);
             self.users_mut().put(
                 &user.public_key,
                 User {
                     public_key: user.public_key,
                     name: user.name,
-                    balance: user.balance + balance,
-                    reserved: user.reserved,
+                    balance,
+                    reserved,
                     last_fillup,
                 },
             );
         }
 
-        /// Helper method to decrease user balance.
-        pub fn decrease_user_balance(&mut self, user_id: &PublicKey, balance: u64) {
-            let user = self.users().get(user_id).expect("User should be exist.");
-            self.users_mut().put(
-                &user.public_key,
-                User {
-                    public_key: user.public_key,
-                    name: user.name,
-                    balance: user.balance - balance,
-                    reserved: user.reserved,
-                    last_fillup: user.last_fillup,
-                },
-            );
+        /// Helper method to close the auction once its time is over. The auction is closed
+        /// when the block is committed, so its events are not caused by a transaction.
+        pub fn close_auction(&mut self, auction_id: u64) -> Result<(), ErrorKind> {
+            let ts = current_time(self.view).ok_or_else(|| ErrorKind::TimeNotAvailable)?;
+            // Check if auction exists.
+            let auction_state = self
+                .auctions()
+                .get(auction_id)
+                .expect("Auction with the given id should be exist.");
+
+            assert!(!auction_state.closed);
+            assert!(ts >= auction_state.ends_at);
+
+            self.settle_auction(auction_id, &Hash::zero())
         }
 
-        /// Helper method to decrease user reserved balance.
-        pub fn reserve_user_balance(&mut self, user_id: &PublicKey, reserve: u64) {
-            let user = self.users().get(user_id).expect("User should be exist.");
-            self.users_mut().put(
-                &user.public_key,
-                User {
-                    public_key: user.public_key,
-                    name: user.name,
-                    balance: user.balance - reserve,
-                    reserved: user.reserved + reserve,
-                    last_fillup: user.last_fillup,
-                },
-            );
+        /// Helper method to settle the auction with its highest bid. The owl is sold
+        /// if the bid reaches the reserve price, otherwise the bid is refunded.
+        /// The reserve price which was not revealed in time is not enforced.
+        ///
+        /// The seller is paid first: it is the only step which may fail without
+        /// a broken invariant, and nothing is changed in this case.
+        pub fn settle_auction(&mut self, auction_id: u64, tx_hash: &Hash) -> Result<(), ErrorKind> {
+            let auction_state = self
+                .auctions()
+                .get(auction_id)
+                .expect("Auction with the given id should be exist.");
+
+            assert!(!auction_state.closed);
+
+            if auction_state.auction.kind.is_sealed() {
+                return self.settle_sealed_auction(auction_state, tx_hash);
+            }
+
+            let close_reason = match self.auction_bids(auction_state.id).last() {
+                Some(ref bid) if bid.value < auction_state.auction.reserve_price => {
+                    // Return reserved funds to the bidder.
+                    self.release_user_balance(&bid.public_key, bid.value)?;
+                    CloseReason::ReserveNotMet
+                }
+                Some(winner_bid) => {
+                    // Increase seller balance.
+                    let seller = &auction_state.auction.public_key;
+                    self.increase_user_balance(seller, winner_bid.value, None)?;
+
+                    // Decrease winner balance.
+                    self.confirm_user_bid(&winner_bid.public_key, winner_bid.value)?;
+
+                    // Pass the owl to the winner.
+                    self.change_owl_owner(&auction_state.auction.owl_id, &winner_bid.public_key);
+                    self.record_sale(
+                        tx_hash,
+                        &auction_state.auction.owl_id,
+                        seller,
+                        &winner_bid.public_key,
+                        winner_bid.value,
+                    );
+                    CloseReason::Sold
+                }
+                None => CloseReason::Expired,
+            };
+
+            self.finish_auction(auction_state, close_reason, tx_hash);
+            Ok(())
         }
 
-        /// Helper method to decrease user reserved balance.
-        pub fn release_user_balance(&mut self, user_id: &PublicKey, reserve: u64) {
-            let user = self.users().get(user_id).expect("User should be exist.");
-            self.users_mut().put(
-                &user.public_key,
-                User {
-                    public_key: user.public_key,
-                    name: user.name,
-                    balance: user.balance + reserve,
-                    reserved: user.reserved - reserve,
-                    last_fillup: user.last_fillup,
-                },
-            );
+        /// Helper method to settle the sealed-bid auction once the reveal phase is over.
+        /// The highest revealed bid wins; the earliest revealed one wins a tie.
+        /// All deposits except the price paid by the winner are returned.
+        fn settle_sealed_auction(
+            &mut self,
+            auction_state: AuctionState,
+            tx_hash: &Hash,
+        ) -> Result<(), ErrorKind> {
+            let auction = auction_state.auction.clone();
+
+            // Revealed bids sorted by value in descending order.
+            let mut revealed = self
+                .auction_bids(auction_state.id)
+                .iter()
+                .collect::<Vec<_>>();
+            revealed.sort_by(|a, b| b.value.cmp(&a.value));
+
+            // The winner and the price to pay.
+            let sale = revealed
+                .first()
+                .filter(|bid| bid.value >= auction.reserve_price)
+                .map(|bid| {
+                    let price = match auction.kind {
+                        AuctionKind::SealedSecondPrice => revealed
+                            .get(1)
+                            .map_or(auction.start_price, |bid| bid.value)
+                            .max(auction.reserve_price),
+                        _ => bid.value,
+                    };
+                    (bid.public_key, price)
+                });
+
+            // Increase seller balance.
+            if let Some((_, price)) = sale {
+                self.increase_user_balance(&auction.public_key, price, None)?;
+            }
+
+            // Return deposits.
+            let deposits = self
+                .sealed_bids(auction_state.id)
+                .values()
+                .collect::<Vec<_>>();
+            for sealed_bid in deposits {
+                let paid = match sale {
+                    Some((winner, price)) if winner == sealed_bid.public_key => price,
+                    _ => 0,
+                };
+                self.release_user_balance(&sealed_bid.public_key, sealed_bid.deposit - paid)?;
+                if paid > 0 {
+                    self.confirm_user_bid(&sealed_bid.public_key, paid)?;
+                }
+            }
+
+            let close_reason = match sale {
+                Some((winner, price)) => {
+                    // Pass the owl to the winner.
+                    self.change_owl_owner(&auction.owl_id, &winner);
+                    self.record_sale(
+                        tx_hash,
+                        &auction.owl_id,
+                        &auction.public_key,
+                        &winner,
+                        price,
+                    );
+                    CloseReason::Sold
+                }
+                None if revealed.is_empty() => CloseReason::Expired,
+                None => CloseReason::ReserveNotMet,
+            };
+
+            self.finish_auction(auction_state, close_reason, tx_hash);
+            Ok(())
         }
 
-        /// Helper method to decrease user bid with value.
-        pub fn confirm_user_bid(&mut self, user_id: &PublicKey, bid_value: u64) {
-            let user = self.users().get(user_id).expect("User should be exist.");
-            self.users_mut().put(
-                &user.public_key,
-                User {
-                    public_key: user.public_key,
-                    name: user.name,
-                    balance: user.balance,
-                    reserved: user.reserved - bid_value,
-                    last_fillup: user.last_fillup,
+        /// Helper method to move the sealed-bid auction (or the auction with a reserve price)
+        /// from the bidding phase to the reveal phase.
+        pub fn start_reveal_phase(&mut self, auction_id: u64) {
+            let auction_state = self
+                .auctions()
+                .get(auction_id)
+                .expect("Auction with the given id should be exist.");
+
+            assert_eq!(auction_state.phase, AuctionPhase::Bidding);
+            assert!(auction_state.auction.has_reveal_phase());
+
+            let reveal_duration = Duration::seconds(auction_state.auction.reveal_duration as i64);
+            let ends_at = auction_state.ends_at + reveal_duration;
+            self.reschedule_auction(auction_state.id, auction_state.ends_at, ends_at);
+            self.auctions_mut().set(
+                auction_state.id,
+                AuctionState {
+                    id: auction_state.id,
+                    auction: auction_state.auction,
+                    started_at: auction_state.started_at,
+                    bidding_merkle_root: auction_state.bidding_merkle_root,
+                    closed: auction_state.closed,
+                    close_reason: auction_state.close_reason,
+                    ends_at,
+                    phase: AuctionPhase::Reveal,
+                    commitments_merkle_root: auction_state.commitments_merkle_root,
                 },
             );
         }
 
-        pub fn close_auction(&mut self, auction_id: u64) {
-            let ts = current_time(self.view).unwrap();
-            // Check if auction exists.
+        /// Helper method to cancel the auction. Reserved funds of the highest bidder
+        /// (or deposits of all sealed bids) are released and the owl stays with the seller.
+        pub fn cancel_auction(&mut self, auction_id: u64, tx_hash: &Hash) -> Result<(), ErrorKind> {
             let auction_state = self
                 .auctions()
                 .get(auction_id)
                 .expect("Auction with the given id should be exist.");
 
             assert!(!auction_state.closed);
-            let auction_ends_at = auction_state.ends_at();
-            assert!(ts >= auction_ends_at);
 
-            if let Some(winner_bid) = self.auction_bids(auction_state.id).last() {
-                // Decrease winner balance.
-                let winner = self.users().get(&winner_bid.public_key).unwrap();
-                self.confirm_user_bid(&winner.public_key, winner_bid.value);
+            if auction_state.auction.kind.is_sealed() {
+                let deposits = self
+                    .sealed_bids(auction_state.id)
+                    .values()
+                    .collect::<Vec<_>>();
+                for sealed_bid in deposits {
+                    self.release_user_balance(&sealed_bid.public_key, sealed_bid.deposit)?;
+                }
+            } else if let Some(top_bid) = self.auction_bids(auction_state.id).last() {
+                self.release_user_balance(&top_bid.public_key, top_bid.value)?;
+            }
 
-                // Increase seller balance.
-                let seller = self.users().get(&auction_state.auction.public_key).unwrap();
-                self.increase_user_balance(&seller.public_key, winner_bid.value, None);
+            self.finish_auction(auction_state, CloseReason::Cancelled, tx_hash);
+            Ok(())
+        }
 
-                // Remove possession from the seller.
-                self.user_owls_mut(&seller.public_key)
-                    .remove(&auction_state.auction.owl_id);
+        /// Helper method to refresh Merkle roots of the auction bids in its state.
+        pub fn refresh_auction_roots(&mut self, auction_state: AuctionState) {
+            let bidding_merkle_root = self.auction_bids(auction_state.id).merkle_root();
+            let commitments_merkle_root = self.sealed_bids(auction_state.id).merkle_root();
+            self.auctions_mut().set(
+                auction_state.id,
+                AuctionState {
+                    id: auction_state.id,
+                    auction: auction_state.auction,
+                    started_at: auction_state.started_at,
+                    bidding_merkle_root,
+                    closed: auction_state.closed,
+                    close_reason: auction_state.close_reason,
+                    ends_at: auction_state.ends_at,
+                    phase: auction_state.phase,
+                    commitments_merkle_root,
+                },
+            );
+        }
 
-                // Pass it to the winner.
-                self.user_owls_mut(&winner.public_key)
-                    .insert(auction_state.auction.owl_id);
+        /// Helper method to move the open auction in the expiry index
+        /// once the end time of its current phase changes.
+        pub fn reschedule_auction(
+            &mut self,
+            auction_id: u64,
+            ends_at: DateTime<Utc>,
+            new_ends_at: DateTime<Utc>,
+        ) {
+            if ends_at == new_ends_at {
+                return;
+            }
+            let mut auction_expiry = self.auction_expiry_mut();
+            auction_expiry.remove(&AuctionExpiry {
+                ends_at,
+                auction_id,
+            });
+            auction_expiry.insert(AuctionExpiry {
+                ends_at: new_ends_at,
+                auction_id,
+            });
+        }
 
-                // Change owl owner.
-                let owl_state = self
-                    .owls_state()
-                    .get(&auction_state.auction.owl_id)
-                    .unwrap();
-                self.owls_state_mut().put(
-                    &auction_state.auction.owl_id,
-                    CryptoOwlState {
-                        owl: owl_state.owl,
-                        owner: winner.public_key,
-                        last_breeding: owl_state.last_breeding,
-                    },
+        /// Helper method to mark the auction as closed and release the owl.
+        /// The seller and the bidders who had funds reserved in the auction
+        /// are notified.
+        fn finish_auction(
+            &mut self,
+            auction_state: AuctionState,
+            close_reason: CloseReason,
+            tx_hash: &Hash,
+        ) {
+            let mut participants = vec![auction_state.auction.public_key];
+            if auction_state.auction.kind.is_sealed() {
+                participants.extend(self.sealed_bids(auction_state.id).keys());
+            } else if let Some(top_bid) = self.auction_bids(auction_state.id).last() {
+                participants.push(top_bid.public_key);
+            }
+            for public_key in &participants {
+                self.record_event(
+                    public_key,
+                    UserEvent::new(EventKind::AuctionClosed, *tx_hash)
+                        .with_owl(auction_state.auction.owl_id)
+                        .with_auction(auction_state.id)
+                        .with_close_reason(close_reason),
                 );
-            };
+            }
 
             self.owl_auction_mut().remove(&auction_state.auction.owl_id);
-            // Close auction
+            self.auction_expiry_mut().remove(&AuctionExpiry {
+                ends_at: auction_state.ends_at,
+                auction_id: auction_state.id,
+            });
             self.auctions_mut().set(
                 auction_state.id,
                 AuctionState {
@@ -802,17 +3873,60 @@ pub mod transactions {
                     started_at: auction_state.started_at,
                     bidding_merkle_root: auction_state.bidding_merkle_root,
                     closed: true,
+                    close_reason,
+                    ends_at: auction_state.ends_at,
+                    phase: AuctionPhase::Closed,
+                    commitments_merkle_root: auction_state.commitments_merkle_root,
                 },
             );
         }
-    }
 
-    impl AuctionState {
-        pub fn ends_at(&self) -> DateTime<Utc> {
-            self.started_at + Duration::seconds(self.auction.duration as i64)
+        /// Helper method to close the listing and release the owl.
+        pub fn close_listing(&mut self, listing_state: ListingState, close_reason: CloseReason) {
+            self.owl_listing_mut().remove(&listing_state.listing.owl_id);
+            self.listings_mut().set(
+                listing_state.id,
+                ListingState {
+                    id: listing_state.id,
+                    listing: listing_state.listing,
+                    listed_at: listing_state.listed_at,
+                    closed: true,
+                    close_reason,
+                },
+            );
         }
     }
 
+    /// Computes the commitment to a sealed bid. The bidder key is included so that
+    /// a commitment can not be copied by other participants.
+    pub fn sealed_bid_commitment(value: u64, salt: &Hash, bidder: &PublicKey) -> Hash {
+        let mut data = Vec::with_capacity(8 + salt.as_ref().len() + bidder.as_ref().len());
+        data.write_u64::<BigEndian>(value).unwrap();
+        data.extend_from_slice(salt.as_ref());
+        data.extend_from_slice(bidder.as_ref());
+        crypto::hash(&data)
+    }
+
+    /// Computes the commitment to the reserve price. It is built the same way
+    /// as the commitment to a sealed bid, with the seller key.
+    pub fn reserve_price_commitment(reserve_price: u64, salt: &Hash, seller: &PublicKey) -> Hash {
+        sealed_bid_commitment(reserve_price, salt, seller)
+    }
+
+    /// Normalizes the owl name for the uniqueness check. Surrounding whitespace is trimmed,
+    /// inner whitespace is collapsed to single spaces and letters are lowercased.
+    pub fn normalize_owl_name(name: &str) -> String {
+        name.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    }
+
+    /// Key of the owl name in the `owl_names` index.
+    pub fn owl_name_key(name: &str) -> Hash {
+        crypto::hash(normalize_owl_name(name).as_bytes())
+    }
+
     // A helper function to get current time from the time oracle.
     pub fn current_time(snapshot: &dyn Snapshot) -> Option<DateTime<Utc>> {
         let time_schema = TimeSchema::new(snapshot);
@@ -865,51 +3979,471 @@ pub mod transactions {
         //
         #[fail(display = "You may not bid on your own item.")]
         NoSelfBidding = 15,
+        //
+        #[fail(display = "Receiver is not registered")]
+        ReceiverIsNotRegistered = 16,
+        //
+        #[fail(display = "You may not transfer to yourself.")]
+        SelfTransfer = 17,
+        //
+        #[fail(display = "Auction has bids and can not be cancelled")]
+        AuctionHasBids = 18,
+        //
+        #[fail(display = "Owl is already listed for sale")]
+        OwlAlreadyListed = 19,
+        //
+        #[fail(display = "Listing does not exist")]
+        ListingNotFound = 20,
+        //
+        #[fail(display = "Listing is closed")]
+        ListingClosed = 21,
+        //
+        #[fail(display = "You may not buy your own item.")]
+        NoSelfBuying = 22,
+        //
+        #[fail(display = "Buy-out price should be above the start price")]
+        InvalidAuctionPrices = 23,
+        //
+        #[fail(display = "Operation is not supported by the auction kind")]
+        WrongAuctionKind = 24,
+        //
+        #[fail(display = "Operation is not allowed in the current auction phase")]
+        WrongAuctionPhase = 25,
+        //
+        #[fail(display = "Bid is already committed")]
+        BidAlreadyCommitted = 26,
+        //
+        #[fail(display = "Bid is not committed")]
+        BidNotCommitted = 27,
+        //
+        #[fail(display = "Bid is already revealed")]
+        BidAlreadyRevealed = 28,
+        //
+        #[fail(display = "Revealed bid does not match the commitment")]
+        CommitmentMismatch = 29,
+        //
+        #[fail(display = "Revealed bid exceeds the deposit")]
+        BidExceedsDeposit = 30,
+        //
+        #[fail(display = "Auction duration is invalid")]
+        InvalidAuctionDuration = 31,
+        //
+        #[fail(display = "Time is not available from the time oracle")]
+        TimeNotAvailable = 32,
+        //
+        #[fail(display = "Balance overflow")]
+        BalanceOverflow = 33,
+        //
+        #[fail(display = "Owls are too closely related")]
+        Inbreeding = 34,
+        //
+        #[fail(display = "Stud offer not found")]
+        StudOfferNotFound = 35,
+        //
+        #[fail(display = "Stud fee does not match the offer")]
+        StudFeeMismatch = 36,
+        //
+        #[fail(display = "Owl name is already taken")]
+        OwlNameTaken = 37,
+        //
+        #[fail(display = "Reserve price is not set")]
+        ReserveNotCommitted = 38,
+        //
+        #[fail(display = "Floor price should not be above the start price")]
+        InvalidFloorPrice = 39,
+        //
+        #[fail(display = "Name should not be empty")]
+        EmptyName = 40,
+    }
+
+    impl ErrorKind {
+        /// Converts error to the raw code.
+        pub fn as_code(&self) -> u8 {
+            self.to_u8().unwrap()
+        }
+    }
+
+    impl From<ErrorKind> for ExecutionError {
+        fn from(e: ErrorKind) -> ExecutionError {
+            let err_txt = format!("{}", e);
+            ExecutionError::with_description(e.as_code(), err_txt)
+        }
+    }
+}
+
+/// Module with API implementation.
+pub mod api {
+    use std::collections::BTreeSet;
+    use std::iter;
+    use std::sync::Arc;
+
+    use actix_web::{http::Method, FromRequest, HttpResponse, Query};
+    use failure::format_err;
+    use futures::IntoFuture;
+    use serde_derive::{Deserialize, Serialize};
+
+    use exonum::api::backends::actix::{FutureResponse, HttpRequest, RawHandler, RequestHandler};
+    use exonum::api::{self, ServiceApiBackend, ServiceApiBuilder, ServiceApiState};
+    use exonum::blockchain::{self, BlockProof, Blockchain};
+    use exonum::crypto::{Hash, PublicKey};
+    use exonum::storage::{ListProof, MapProof, Snapshot};
+
+    use crate::{
+        data_layout::{
+            AuctionKind, AuctionState, Bid, CryptoOwlState, Egg, ListingState, OwlMetadata,
+            StudOffer, TraitStats, User, UserEvent,
+        },
+        genetics::Traits,
+        render::{self, SVG_CONTENT_TYPE},
+        schema::{
+            CryptoOwlsSchema, AUCTIONS_TABLE_INDEX, OWLS_STATE_TABLE_INDEX, USERS_TABLE_INDEX,
+            USER_HISTORY_ROOTS_TABLE_INDEX,
+        },
+        transactions::{current_time, owl_name_key},
+        CRYPTOOWLS_SERVICE_ID,
+    };
+
+    #[derive(Debug)]
+    pub struct CryptoOwlsApi;
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    pub struct OwlQuery {
+        pub id: Hash,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct OwlNameQuery {
+        pub name: String,
+    }
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    pub struct PedigreeQuery {
+        pub id: Hash,
+        pub depth: Option<u64>,
+    }
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    pub struct UserQuery {
+        pub pub_key: PublicKey,
+    }
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    pub struct HistoryQuery {
+        pub pub_key: PublicKey,
+        pub offset: Option<u64>,
+        pub limit: Option<u64>,
+    }
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    pub struct AuctionQuery {
+        pub id: u64,
+    }
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    pub struct BidQuery {
+        pub auction_id: u64,
+        pub index: Option<u64>,
+    }
+
+    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+    pub struct ListingQuery {
+        pub id: u64,
+    }
+
+    /// Number of ancestor generations in the pedigree if the depth is not specified.
+    pub const DEFAULT_PEDIGREE_DEPTH: u64 = 3;
+
+    /// Maximal number of ancestor generations in the pedigree.
+    pub const MAX_PEDIGREE_DEPTH: u64 = 8;
+
+    /// Number of history events on the page if the limit is not specified.
+    pub const DEFAULT_HISTORY_LIMIT: u64 = 50;
+
+    /// Maximal number of history events on the page.
+    pub const MAX_HISTORY_LIMIT: u64 = 1000;
+
+    /// Page of the user history.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct HistoryPage {
+        /// Total number of events in the history.
+        pub total: u64,
+        /// Events starting from the requested offset, in order of occurrence.
+        pub events: Vec<UserEvent>,
+    }
+
+    /// Owls of the family tree with proofs from the service tables. The metadata
+    /// links owls with their parents, so the tree can be verified by a client.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct OwlFamily {
+        /// Proof for the family members in `owls_state`.
+        pub owls: MapProof<Hash, CryptoOwlState>,
+        /// Proof for the family members in `owls_metadata`.
+        pub metadata: MapProof<Hash, OwlMetadata>,
+    }
+
+    fn owl_family<T>(schema: &CryptoOwlsSchema<T>, owls: Vec<Hash>) -> OwlFamily
+    where
+        T: AsRef<dyn Snapshot>,
+    {
+        OwlFamily {
+            owls: schema.owls_state().get_multiproof(owls.clone()),
+            metadata: schema.owls_metadata().get_multiproof(owls),
+        }
+    }
+
+    /// Proof of the user in the `users` table and of the table in the blockchain state.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct UserProof {
+        /// Proof of the `users` table root in the `state_hash_aggregator`.
+        pub to_table: MapProof<Hash, Hash>,
+        /// Proof of the user (or of their absence) in the `users` table.
+        pub to_user: MapProof<PublicKey, User>,
+    }
+
+    /// User profile which can be verified by a light client.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct UserInfo {
+        /// The latest block with precommits of the validators.
+        pub block_proof: BlockProof,
+        /// Proof of the user against the state hash of the block.
+        pub user_proof: UserProof,
+    }
+
+    /// Proof of the owl in the `owls_state` table and of the table in the blockchain state.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct OwlProof {
+        /// Proof of the `owls_state` table root in the `state_hash_aggregator`.
+        pub to_table: MapProof<Hash, Hash>,
+        /// Proof of the owl (or of its absence) in the `owls_state` table.
+        pub to_owl: MapProof<Hash, CryptoOwlState>,
+    }
+
+    /// Owl state (including the owner) which can be verified by a light client.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct OwlStateInfo {
+        /// The latest block with precommits of the validators.
+        pub block_proof: BlockProof,
+        /// Proof of the owl against the state hash of the block.
+        pub owl_proof: OwlProof,
+    }
+
+    /// Proof of the auction in the `auctions` table and of the table in the blockchain state.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct AuctionProof {
+        /// Proof of the `auctions` table root in the `state_hash_aggregator`.
+        pub to_table: MapProof<Hash, Hash>,
+        /// Proof of the auction in the `auctions` table.
+        pub to_auction: ListProof<AuctionState>,
+        /// Number of auctions, which is needed to check `to_auction`.
+        pub auctions_count: u64,
+    }
+
+    /// Auction state which can be verified by a light client. Until the seller reveals
+    /// the reserve price, the proven auction holds only the commitment to it.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct AuctionInfo {
+        /// The latest block with precommits of the validators.
+        pub block_proof: BlockProof,
+        /// Proof of the auction against the state hash of the block.
+        pub auction_proof: AuctionProof,
+    }
+
+    /// Proof of the bid in the bids history of the auction. The history is authenticated
+    /// through `AuctionState::bidding_merkle_root` rather than the state hash itself.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct BidProof {
+        /// Proof of the bid in the `auction_bids` list.
+        pub to_bid: ListProof<Bid>,
+        /// Number of bids, which is needed to check `to_bid`.
+        pub bids_count: u64,
     }
 
-    impl ErrorKind {
-        /// Converts error to the raw code.
-        pub fn as_code(&self) -> u8 {
-            self.to_u8().unwrap()
+    /// Auction bid which can be verified by a light client (see `BidInfo::verify`).
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct BidInfo {
+        /// The latest block with precommits of the validators.
+        pub block_proof: BlockProof,
+        /// Proof of the auction against the state hash of the block.
+        pub auction_proof: AuctionProof,
+        /// Proof of the bid against the bidding Merkle root of the auction.
+        pub bid_proof: BidProof,
+    }
+
+    /// Proof of the page of the user history. The history is authenticated through
+    /// its Merkle root in the `user_history_roots` table.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct HistoryProof {
+        /// Proof of the `user_history_roots` table root in the `state_hash_aggregator`.
+        pub to_table: MapProof<Hash, Hash>,
+        /// Proof of the history root in the `user_history_roots` table.
+        pub to_history: MapProof<PublicKey, Hash>,
+        /// Proof of the events of the page in the `user_history` list.
+        pub to_events: ListProof<UserEvent>,
+        /// Total number of events in the history, which is needed to check `to_events`.
+        pub total: u64,
+    }
+
+    /// Page of the user history which can be verified by a light client
+    /// (see `HistoryInfo::verify`).
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct HistoryInfo {
+        /// The latest block with precommits of the validators.
+        pub block_proof: BlockProof,
+        /// Proof of the events against the state hash of the block.
+        pub history_proof: HistoryProof,
+    }
+
+    /// Checks the proof of the service table against the state hash
+    /// and returns the Merkle root of the table.
+    fn check_table_proof(
+        to_table: MapProof<Hash, Hash>,
+        state_hash: &Hash,
+        table_idx: usize,
+    ) -> Result<Hash, failure::Error> {
+        let to_table = to_table.check()?;
+        if to_table.merkle_root() != *state_hash {
+            return Err(format_err!("Table proof does not match the state hash"));
         }
+        let key = Blockchain::service_table_unique_key(CRYPTOOWLS_SERVICE_ID, table_idx);
+        to_table
+            .entries()
+            .find(|(table_key, _)| **table_key == key)
+            .map(|(_, table_root)| *table_root)
+            .ok_or_else(|| format_err!("Table root is not proven"))
     }
 
-    impl From<ErrorKind> for ExecutionError {
-        fn from(e: ErrorKind) -> ExecutionError {
-            let err_txt = format!("{}", e);
-            ExecutionError::with_description(e.as_code(), err_txt)
+    /// Checks the proof of the single element of the list and returns the element
+    /// with its index.
+    fn check_list_proof<V: Clone>(
+        proof: &ListProof<V>,
+        merkle_root: Hash,
+        len: u64,
+    ) -> Result<(u64, V), failure::Error> {
+        let elements = proof
+            .validate(merkle_root, len)
+            .map_err(|e| format_err!("Invalid list proof: {:?}", e))?;
+        match elements.as_slice() {
+            [(index, value)] => Ok((*index, (*value).clone())),
+            _ => Err(format_err!("List proof should contain a single element")),
         }
     }
-}
 
-/// Module with API implementation.
-mod api {
-    use serde_derive::{Deserialize, Serialize};
+    impl AuctionProof {
+        /// Checks the proof against the state hash of the block
+        /// and returns the auction state.
+        pub fn verify(self, state_hash: &Hash) -> Result<AuctionState, failure::Error> {
+            let table_root = check_table_proof(self.to_table, state_hash, AUCTIONS_TABLE_INDEX)?;
+            let (_, auction_state) =
+                check_list_proof(&self.to_auction, table_root, self.auctions_count)?;
+            Ok(auction_state)
+        }
+    }
 
-    use exonum::api::{self, ServiceApiBuilder, ServiceApiState};
-    use exonum::crypto::{Hash, PublicKey};
+    impl BidProof {
+        /// Checks the proof against the bidding Merkle root of the auction
+        /// and returns the bid with its index in the bids history.
+        pub fn verify(self, auction_state: &AuctionState) -> Result<(u64, Bid), failure::Error> {
+            check_list_proof(
+                &self.to_bid,
+                auction_state.bidding_merkle_root,
+                self.bids_count,
+            )
+        }
+    }
 
-    use crate::{
-        data_layout::{AuctionState, Bid, CryptoOwlState, User},
-        schema::CryptoOwlsSchema,
-    };
+    impl HistoryProof {
+        /// Checks the chain of proofs from the state hash to the events of the user:
+        /// the `user_history_roots` table root and the Merkle root of the history.
+        /// Returns the events with their indexes in the history.
+        pub fn verify(
+            self,
+            state_hash: &Hash,
+            public_key: &PublicKey,
+        ) -> Result<Vec<(u64, UserEvent)>, failure::Error> {
+            let table_root =
+                check_table_proof(self.to_table, state_hash, USER_HISTORY_ROOTS_TABLE_INDEX)?;
+            let to_history = self.to_history.check()?;
+            if to_history.merkle_root() != table_root {
+                return Err(format_err!("History proof does not match the table root"));
+            }
+            let history_root = to_history
+                .entries()
+                .find(|(key, _)| *key == public_key)
+                .map(|(_, history_root)| *history_root)
+                .ok_or_else(|| format_err!("History root is not proven"))?;
+            let events = self
+                .to_events
+                .validate(history_root, self.total)
+                .map_err(|e| format_err!("Invalid list proof: {:?}", e))?;
+            Ok(events
+                .into_iter()
+                .map(|(index, event)| (index, event.clone()))
+                .collect())
+        }
+    }
 
-    #[derive(Debug)]
-    pub struct CryptoOwlsApi;
+    impl HistoryInfo {
+        /// Checks the proofs from the state hash of the block to the events of the user
+        /// (see `HistoryProof::verify`).
+        ///
+        /// Precommits are not checked, since this needs the public keys of the validators.
+        pub fn verify(
+            self,
+            public_key: &PublicKey,
+        ) -> Result<Vec<(u64, UserEvent)>, failure::Error> {
+            self.history_proof
+                .verify(self.block_proof.block.state_hash(), public_key)
+        }
+    }
 
-    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-    pub struct OwlQuery {
-        pub id: Hash,
+    impl BidInfo {
+        /// Checks the chain of proofs from the state hash of the block to the bid:
+        /// the `auctions` table root, the auction state and its bidding Merkle root.
+        /// Returns the auction state, and the bid with its index in the bids history.
+        ///
+        /// Precommits are not checked, since this needs the public keys of the validators.
+        pub fn verify(self) -> Result<(AuctionState, u64, Bid), failure::Error> {
+            let auction_state = self
+                .auction_proof
+                .verify(self.block_proof.block.state_hash())?;
+            let (index, bid) = self.bid_proof.verify(&auction_state)?;
+            Ok((auction_state, index, bid))
+        }
     }
 
-    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-    pub struct UserQuery {
-        pub pub_key: PublicKey,
+    /// The latest block with precommits, and the proof of the service table
+    /// with the given index against the state hash of this block.
+    fn block_and_table_proof(
+        snapshot: &dyn Snapshot,
+        table_idx: usize,
+    ) -> (BlockProof, MapProof<Hash, Hash>) {
+        let general_schema = blockchain::Schema::new(snapshot);
+        let block_proof = general_schema
+            .block_and_precommits(general_schema.height())
+            .unwrap();
+        let to_table = general_schema.get_proof_to_service_table(CRYPTOOWLS_SERVICE_ID, table_idx);
+        (block_proof, to_table)
     }
 
-    #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-    pub struct AuctionQuery {
-        pub id: u64,
+    /// Owl state with the traits decoded from its DNA.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct OwlInfo {
+        /// Owl state as stored in `owls_state`.
+        #[serde(flatten)]
+        pub state: CryptoOwlState,
+        /// Traits of the owl.
+        pub traits: Traits,
+        /// Rarity score of the owl traits (see `TraitStats::rarity`).
+        pub rarity: f64,
+    }
+
+    fn owl_info(stats: &TraitStats, owl_state: CryptoOwlState) -> OwlInfo {
+        let traits = Traits::from(&owl_state.owl);
+        OwlInfo {
+            rarity: stats.rarity(&traits),
+            traits,
+            state: owl_state,
+        }
     }
 
     impl CryptoOwlsApi {
@@ -920,6 +4454,21 @@ mod api {
             Ok(schema.users().get(&query.pub_key))
         }
 
+        /// User profile with proofs (see `UserInfo`).
+        fn get_user_info(state: &ServiceApiState, query: UserQuery) -> api::Result<UserInfo> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(&snapshot);
+            let (block_proof, to_table) =
+                block_and_table_proof(snapshot.as_ref(), USERS_TABLE_INDEX);
+            Ok(UserInfo {
+                block_proof,
+                user_proof: UserProof {
+                    to_table,
+                    to_user: schema.users().get_proof(query.pub_key),
+                },
+            })
+        }
+
         /// All users.
         fn get_users(state: &ServiceApiState, _query: ()) -> api::Result<Vec<User>> {
             let snapshot = state.snapshot();
@@ -930,21 +4479,124 @@ mod api {
         }
 
         /// Owl profile.
-        fn get_owl(
+        fn get_owl(state: &ServiceApiState, query: OwlQuery) -> api::Result<Option<OwlInfo>> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(snapshot);
+            let stats = schema.owl_trait_stats();
+            Ok(schema
+                .owls_state()
+                .get(&query.id)
+                .map(|owl_state| owl_info(&stats, owl_state)))
+        }
+
+        /// Owl state with proofs (see `OwlStateInfo`).
+        fn get_owl_info(state: &ServiceApiState, query: OwlQuery) -> api::Result<OwlStateInfo> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(&snapshot);
+            let (block_proof, to_table) =
+                block_and_table_proof(snapshot.as_ref(), OWLS_STATE_TABLE_INDEX);
+            Ok(OwlStateInfo {
+                block_proof,
+                owl_proof: OwlProof {
+                    to_table,
+                    to_owl: schema.owls_state().get_proof(query.id),
+                },
+            })
+        }
+
+        /// Identifier of the owl (or the egg) with the given name.
+        fn get_owl_by_name(
+            state: &ServiceApiState,
+            query: OwlNameQuery,
+        ) -> api::Result<Option<Hash>> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(snapshot);
+            Ok(schema.owl_names().get(&owl_name_key(&query.name)))
+        }
+
+        /// Owl image in SVG.
+        fn get_owl_image(request: HttpRequest) -> FutureResponse {
+            let response = Query::<OwlQuery>::extract(&request).and_then(|query| {
+                let schema = CryptoOwlsSchema::new(request.state().snapshot());
+                let owl_state = schema
+                    .owls_state()
+                    .get(&query.id)
+                    .ok_or_else(|| api::Error::NotFound("Owl not found".to_owned()))?;
+                Ok(HttpResponse::Ok()
+                    .content_type(SVG_CONTENT_TYPE)
+                    .body(render::owl_svg(owl_state.owl.dna)))
+            });
+            Box::new(response.into_future())
+        }
+
+        /// Owl and its ancestors.
+        fn get_owl_pedigree(
+            state: &ServiceApiState,
+            query: PedigreeQuery,
+        ) -> api::Result<Option<OwlFamily>> {
+            let depth = query.depth.unwrap_or(DEFAULT_PEDIGREE_DEPTH);
+            if depth > MAX_PEDIGREE_DEPTH {
+                return Err(api::Error::BadRequest(format!(
+                    "Pedigree depth should not exceed {}",
+                    MAX_PEDIGREE_DEPTH
+                )));
+            }
+
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(snapshot);
+            if !schema.owls_state().contains(&query.id) {
+                return Ok(None);
+            }
+
+            // Walk the tree generation by generation. Owls may share ancestors.
+            let mut family = BTreeSet::new();
+            family.insert(query.id);
+            let mut generation = vec![query.id];
+            for _ in 0..depth {
+                let mut parents = Vec::new();
+                for metadata in generation
+                    .iter()
+                    .filter_map(|id| schema.owls_metadata().get(id))
+                {
+                    for parent_id in &[metadata.father_id, metadata.mother_id] {
+                        if *parent_id != Hash::zero() && family.insert(*parent_id) {
+                            parents.push(*parent_id);
+                        }
+                    }
+                }
+                generation = parents;
+            }
+
+            Ok(Some(owl_family(&schema, family.into_iter().collect())))
+        }
+
+        /// Owl and its children.
+        fn get_owl_children(
             state: &ServiceApiState,
             query: OwlQuery,
-        ) -> api::Result<Option<CryptoOwlState>> {
+        ) -> api::Result<Option<OwlFamily>> {
             let snapshot = state.snapshot();
             let schema = CryptoOwlsSchema::new(snapshot);
-            Ok(schema.owls_state().get(&query.id))
+            if !schema.owls_state().contains(&query.id) {
+                return Ok(None);
+            }
+
+            let family = iter::once(query.id)
+                .chain(schema.owl_children(&query.id).iter().map(|(_, id)| id))
+                .collect();
+            Ok(Some(owl_family(&schema, family)))
         }
 
         /// All owls.
-        fn get_owls(state: &ServiceApiState, _query: ()) -> api::Result<Vec<CryptoOwlState>> {
+        fn get_owls(state: &ServiceApiState, _query: ()) -> api::Result<Vec<OwlInfo>> {
             let snapshot = state.snapshot();
             let schema = CryptoOwlsSchema::new(snapshot);
+            let stats = schema.owl_trait_stats();
             let idx = schema.owls_state();
-            let owls: Vec<CryptoOwlState> = idx.values().collect();
+            let owls: Vec<OwlInfo> = idx
+                .values()
+                .map(|owl_state| owl_info(&stats, owl_state))
+                .collect();
             Ok(owls)
         }
 
@@ -952,20 +4604,131 @@ mod api {
         fn get_user_owls(
             state: &ServiceApiState,
             query: UserQuery,
-        ) -> api::Result<Option<Vec<CryptoOwlState>>> {
+        ) -> api::Result<Option<Vec<OwlInfo>>> {
             let snapshot = state.snapshot();
             let schema = CryptoOwlsSchema::new(snapshot);
+            let stats = schema.owl_trait_stats();
 
             Ok(schema.users().get(&query.pub_key).and({
                 let idx = schema.user_owls(&query.pub_key);
                 // Attention, iterator type is ValueSetIndexIter<'_, Hash> !!!
                 idx.iter()
-                    .map(|h| schema.owls_state().get(&h.1))
-                    .collect::<Option<Vec<CryptoOwlState>>>()
+                    .map(|h| {
+                        schema
+                            .owls_state()
+                            .get(&h.1)
+                            .map(|owl_state| owl_info(&stats, owl_state))
+                    })
+                    .collect::<Option<Vec<OwlInfo>>>()
                     .or_else(|| Some(Vec::new()))
             }))
         }
 
+        /// Events concerning the user, paginated by the offset from the first event.
+        fn get_user_history(
+            state: &ServiceApiState,
+            query: HistoryQuery,
+        ) -> api::Result<Option<HistoryPage>> {
+            let limit = query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+            if limit > MAX_HISTORY_LIMIT {
+                return Err(api::Error::BadRequest(format!(
+                    "History limit should not exceed {}",
+                    MAX_HISTORY_LIMIT
+                )));
+            }
+
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(snapshot);
+
+            Ok(schema.users().get(&query.pub_key).map(|user| {
+                let history = schema.user_history(&user.public_key);
+                let events = history
+                    .iter_from(query.offset.unwrap_or(0))
+                    .take(limit as usize)
+                    .collect();
+                HistoryPage {
+                    total: history.len(),
+                    events,
+                }
+            }))
+        }
+
+        /// Page of the user history with proofs (see `HistoryInfo`). Same as
+        /// `get_user_history`, but the page should not be empty.
+        fn get_user_history_info(
+            state: &ServiceApiState,
+            query: HistoryQuery,
+        ) -> api::Result<Option<HistoryInfo>> {
+            let limit = query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+            if limit > MAX_HISTORY_LIMIT {
+                return Err(api::Error::BadRequest(format!(
+                    "History limit should not exceed {}",
+                    MAX_HISTORY_LIMIT
+                )));
+            }
+
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(&snapshot);
+            let history = schema.user_history(&query.pub_key);
+            let offset = query.offset.unwrap_or(0);
+            let end = offset.saturating_add(limit).min(history.len());
+            if offset >= end {
+                return Ok(None);
+            }
+
+            let (block_proof, to_table) =
+                block_and_table_proof(snapshot.as_ref(), USER_HISTORY_ROOTS_TABLE_INDEX);
+            Ok(Some(HistoryInfo {
+                block_proof,
+                history_proof: HistoryProof {
+                    to_table,
+                    to_history: schema.user_history_roots().get_proof(query.pub_key),
+                    to_events: history.get_range_proof(offset, end),
+                    total: history.len(),
+                },
+            }))
+        }
+
+        /// Number of owls with each trait.
+        fn get_trait_stats(state: &ServiceApiState, _query: ()) -> api::Result<TraitStats> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(snapshot);
+            Ok(schema.owl_trait_stats())
+        }
+
+        /// Egg by its identifier.
+        fn get_egg(state: &ServiceApiState, query: OwlQuery) -> api::Result<Option<Egg>> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(snapshot);
+            Ok(schema.eggs().get(&query.id))
+        }
+
+        /// All eggs with their identifiers.
+        fn get_eggs(state: &ServiceApiState, _query: ()) -> api::Result<Vec<(Hash, Egg)>> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(snapshot);
+            let idx = schema.eggs();
+            let eggs: Vec<(Hash, Egg)> = idx.iter().collect();
+            Ok(eggs)
+        }
+
+        /// User eggs list.
+        fn get_user_eggs(
+            state: &ServiceApiState,
+            query: UserQuery,
+        ) -> api::Result<Option<Vec<(Hash, Egg)>>> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(snapshot);
+
+            Ok(schema.users().get(&query.pub_key).map(|user| {
+                let user_eggs = schema.user_eggs(&user.public_key);
+                user_eggs
+                    .iter()
+                    .map(|(_, egg_id)| (egg_id, schema.eggs().get(&egg_id).unwrap()))
+                    .collect()
+            }))
+        }
+
         /// Auctions made by user.
         fn get_users_auctions(
             state: &ServiceApiState,
@@ -998,6 +4761,62 @@ mod api {
             }))
         }
 
+        /// Auction state with proofs (see `AuctionInfo`).
+        fn get_auction_info(
+            state: &ServiceApiState,
+            query: AuctionQuery,
+        ) -> api::Result<Option<AuctionInfo>> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(&snapshot);
+            let auctions = schema.auctions();
+            if query.id >= auctions.len() {
+                return Ok(None);
+            }
+
+            let (block_proof, to_table) =
+                block_and_table_proof(snapshot.as_ref(), AUCTIONS_TABLE_INDEX);
+            Ok(Some(AuctionInfo {
+                block_proof,
+                auction_proof: AuctionProof {
+                    to_table,
+                    to_auction: auctions.get_proof(query.id),
+                    auctions_count: auctions.len(),
+                },
+            }))
+        }
+
+        /// Auction bid with proofs (see `BidInfo`). The last bid is returned
+        /// if the index is not specified.
+        fn get_bid_info(state: &ServiceApiState, query: BidQuery) -> api::Result<Option<BidInfo>> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(&snapshot);
+            let auctions = schema.auctions();
+            if query.auction_id >= auctions.len() {
+                return Ok(None);
+            }
+            let bids = schema.auction_bids(query.auction_id);
+            let index = match query.index {
+                Some(index) if index < bids.len() => index,
+                None if !bids.is_empty() => bids.len() - 1,
+                _ => return Ok(None),
+            };
+
+            let (block_proof, to_table) =
+                block_and_table_proof(snapshot.as_ref(), AUCTIONS_TABLE_INDEX);
+            Ok(Some(BidInfo {
+                block_proof,
+                auction_proof: AuctionProof {
+                    to_table,
+                    to_auction: auctions.get_proof(query.auction_id),
+                    auctions_count: auctions.len(),
+                },
+                bid_proof: BidProof {
+                    to_bid: bids.get_proof(index),
+                    bids_count: bids.len(),
+                },
+            }))
+        }
+
         /// Auction bids by its identifier.
         fn get_auction_bids(
             state: &ServiceApiState,
@@ -1012,6 +4831,24 @@ mod api {
             }))
         }
 
+        /// Current price of the open Dutch auction.
+        fn get_auction_price(
+            state: &ServiceApiState,
+            query: AuctionQuery,
+        ) -> api::Result<Option<u64>> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(&snapshot);
+
+            let ts = current_time(snapshot.as_ref());
+            Ok(schema
+                .auctions()
+                .get(query.id)
+                .filter(|auction_state| {
+                    auction_state.auction.kind == AuctionKind::Dutch && !auction_state.closed
+                })
+                .and_then(|auction_state| ts.map(|ts| auction_state.dutch_price(ts))))
+        }
+
         /// All auctions.
         fn get_auctions(state: &ServiceApiState, _query: ()) -> api::Result<Vec<AuctionState>> {
             let snapshot = state.snapshot();
@@ -1021,43 +4858,199 @@ mod api {
             Ok(auctions)
         }
 
+        /// Fixed-price listing by its identifier.
+        fn get_listing(
+            state: &ServiceApiState,
+            query: ListingQuery,
+        ) -> api::Result<Option<ListingState>> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(snapshot);
+            Ok(schema.listings().get(query.id))
+        }
+
+        /// All fixed-price listings.
+        fn get_listings(state: &ServiceApiState, _query: ()) -> api::Result<Vec<ListingState>> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(snapshot);
+            let listings = schema.listings();
+            let listings = listings.into_iter().collect::<Vec<_>>();
+            Ok(listings)
+        }
+
+        /// Fixed-price listings made by user.
+        fn get_users_listings(
+            state: &ServiceApiState,
+            query: UserQuery,
+        ) -> api::Result<Option<Vec<ListingState>>> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(snapshot);
+
+            Ok(schema.users().get(&query.pub_key).map(|user| {
+                let user_listings = schema.user_listings(&user.public_key);
+                user_listings
+                    .into_iter()
+                    .map(|listing_id| schema.listings().get(listing_id).unwrap())
+                    .collect()
+            }))
+        }
+
+        /// Stud offer by the offered owl.
+        fn get_stud(state: &ServiceApiState, query: OwlQuery) -> api::Result<Option<StudOffer>> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(snapshot);
+            Ok(schema.stud_offers().get(&query.id))
+        }
+
+        /// All active stud offers.
+        fn get_studs(state: &ServiceApiState, _query: ()) -> api::Result<Vec<StudOffer>> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(snapshot);
+            let idx = schema.stud_offers();
+            let offers: Vec<StudOffer> = idx.values().collect();
+            Ok(offers)
+        }
+
+        /// Active stud offers made by user.
+        fn get_users_studs(
+            state: &ServiceApiState,
+            query: UserQuery,
+        ) -> api::Result<Option<Vec<StudOffer>>> {
+            let snapshot = state.snapshot();
+            let schema = CryptoOwlsSchema::new(snapshot);
+
+            Ok(schema.users().get(&query.pub_key).map(|user| {
+                let user_offers = schema.user_stud_offers(&user.public_key);
+                user_offers
+                    .iter()
+                    .map(|(_, owl_id)| schema.stud_offers().get(&owl_id).unwrap())
+                    .collect()
+            }))
+        }
+
         // Links the service api implementation to the Exonum.
         pub fn wire(builder: &mut ServiceApiBuilder) {
+            // Images are not JSON, so they are served by the raw handler.
+            builder
+                .public_scope()
+                .web_backend()
+                .raw_handler(RequestHandler {
+                    name: "v1/owl/image".to_owned(),
+                    method: Method::GET,
+                    inner: Arc::from(Self::get_owl_image) as Arc<RawHandler>,
+                });
             builder
                 .public_scope()
                 .endpoint("v1/users", Self::get_users)
                 .endpoint("v1/user", Self::get_user)
+                .endpoint("v1/user/info", Self::get_user_info)
                 .endpoint("v1/owls", Self::get_owls)
                 .endpoint("v1/owl", Self::get_owl)
+                .endpoint("v1/owl/info", Self::get_owl_info)
+                .endpoint("v1/owl/pedigree", Self::get_owl_pedigree)
+                .endpoint("v1/owl/children", Self::get_owl_children)
+                .endpoint("v1/owl/by-name", Self::get_owl_by_name)
                 .endpoint("v1/user/owls", Self::get_user_owls)
+                .endpoint("v1/user/history", Self::get_user_history)
+                .endpoint("v1/user/history/info", Self::get_user_history_info)
+                .endpoint("v1/egg", Self::get_egg)
+                .endpoint("v1/eggs", Self::get_eggs)
+                .endpoint("v1/user/eggs", Self::get_user_eggs)
                 .endpoint("v1/user/auctions", Self::get_users_auctions)
                 .endpoint("v1/auction/bids", Self::get_auction_bids)
+                .endpoint("v1/auction/price", Self::get_auction_price)
                 .endpoint("v1/auction", Self::get_auction_with_bids)
-                .endpoint("v1/auctions", Self::get_auctions);
+                .endpoint("v1/auction/info", Self::get_auction_info)
+                .endpoint("v1/auction/bid/info", Self::get_bid_info)
+                .endpoint("v1/auctions", Self::get_auctions)
+                .endpoint("v1/user/listings", Self::get_users_listings)
+                .endpoint("v1/listing", Self::get_listing)
+                .endpoint("v1/listings", Self::get_listings)
+                .endpoint("v1/user/studs", Self::get_users_studs)
+                .endpoint("v1/stud", Self::get_stud)
+                .endpoint("v1/studs", Self::get_studs)
+                .endpoint("v1/stats/traits", Self::get_trait_stats);
         }
     }
 }
 
 /// Collecting everything together.
 pub mod service {
+    use std::collections::BTreeMap;
+
+    use log::error;
+    use serde_derive::Serialize;
+    use serde_json::Value;
+
     use exonum::{
         api::ServiceApiBuilder,
-        blockchain::{Service, Transaction, TransactionSet},
+        blockchain::{
+            ExecutionResult, Schema, Service, Transaction, TransactionContext, TransactionSet,
+        },
         crypto::Hash,
-        helpers::fabric::{Context, ServiceFactory},
+        helpers::fabric::{
+            self, keys, Argument, Command, CommandExtension, CommandName, Context, ServiceFactory,
+        },
+        helpers::Height,
         messages::RawTransaction,
+        node::NodeConfig,
         storage::{Fork, Snapshot},
     };
 
     use crate::{
         api::CryptoOwlsApi,
+        config::CryptoOwlsConfig,
+        data_layout::AuctionPhase,
+        randomness::{BlockHashRandomness, RandomnessSource},
         schema::CryptoOwlsSchema,
         transactions::{self, Transactions},
-        CRYPTOOWLS_SERVICE_ID, CRYPTOOWLS_SERVICE_NAME,
+        CRYPTOOWLS_SERVICE_ID, CRYPTOOWLS_SERVICE_NAME, SCHEMA_VERSION,
     };
 
-    #[derive(Debug, Default)]
-    pub struct CryptoOwlsService;
+    /// Transaction which migrates the data stored by earlier versions of the service
+    /// (see `CryptoOwlsSchema::migrate`) before it is executed. The first transaction
+    /// of the first block after the upgrade migrates the data, so no transaction runs
+    /// against the old layout. The migration is discarded along with the changes
+    /// of a failed transaction and is then done by the next one.
+    #[derive(Debug, Serialize)]
+    #[serde(transparent)]
+    struct MigratingTransaction(Box<dyn Transaction>);
+
+    impl Transaction for MigratingTransaction {
+        fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+            CryptoOwlsSchema::new(context.fork()).migrate();
+            self.0.execute(context)
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct CryptoOwlsService {
+        /// Initial service configuration written to the genesis block.
+        config: CryptoOwlsConfig,
+        /// Source of randomness to hatch eggs.
+        randomness: Box<dyn RandomnessSource>,
+    }
+
+    impl CryptoOwlsService {
+        /// Creates the service with the given initial configuration.
+        pub fn new(config: CryptoOwlsConfig) -> Self {
+            CryptoOwlsService {
+                config,
+                randomness: Box::new(BlockHashRandomness),
+            }
+        }
+
+        /// Replaces the source of randomness to hatch eggs.
+        pub fn with_randomness<R: RandomnessSource + 'static>(mut self, randomness: R) -> Self {
+            self.randomness = Box::new(randomness);
+            self
+        }
+    }
+
+    impl Default for CryptoOwlsService {
+        fn default() -> Self {
+            CryptoOwlsService::new(CryptoOwlsConfig::default())
+        }
+    }
 
     #[derive(Debug, Default)]
     pub struct CryptoOwlsServiceFactory;
@@ -1067,8 +5060,169 @@ pub mod service {
             CRYPTOOWLS_SERVICE_NAME
         }
 
-        fn make_service(&mut self, _: &Context) -> Box<dyn Service> {
-            Box::new(CryptoOwlsService)
+        fn command(&mut self, command: CommandName) -> Option<Box<dyn CommandExtension>> {
+            Some(match command {
+                v if v == fabric::GenerateCommonConfig.name() => Box::new(GenerateCommonConfig),
+                v if v == fabric::Finalize.name() => Box::new(Finalize),
+                _ => return None,
+            })
+        }
+
+        fn make_service(&mut self, context: &Context) -> Box<dyn Service> {
+            let node_config: NodeConfig = context.get(keys::NODE_CONFIG).unwrap();
+            let config = node_config
+                .services_configs
+                .get(CRYPTOOWLS_SERVICE_NAME)
+                .map(|value| {
+                    parse_config(value).unwrap_or_else(|e| {
+                        panic!("Invalid configuration of the cryptoowls service: {}", e)
+                    })
+                })
+                .unwrap_or_default();
+            Box::new(CryptoOwlsService::new(config))
+        }
+    }
+
+    /// Parses the service section of the node configuration and checks the parameters.
+    fn parse_config(value: &toml::Value) -> Result<CryptoOwlsConfig, failure::Error> {
+        let config: CryptoOwlsConfig = value.clone().try_into()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Adds initial gameplay parameters to the `generate-template` command.
+    struct GenerateCommonConfig;
+
+    impl CommandExtension for GenerateCommonConfig {
+        fn args(&self) -> Vec<Argument> {
+            vec![
+                Argument::new_named(
+                    "ISSUE_AMOUNT",
+                    false,
+                    "Sum to be issued each time.",
+                    None,
+                    "issue-amount",
+                    false,
+                ),
+                Argument::new_named(
+                    "ISSUE_TIMEOUT",
+                    false,
+                    "Timeout (seconds) before user will be able to issue funds again.",
+                    None,
+                    "issue-timeout",
+                    false,
+                ),
+                Argument::new_named(
+                    "BREEDING_TIMEOUT",
+                    false,
+                    "Timeout (seconds) before user will be able to breed owl again.",
+                    None,
+                    "breeding-timeout",
+                    false,
+                ),
+                Argument::new_named(
+                    "BREEDING_PRICE",
+                    false,
+                    "Breeding price.",
+                    None,
+                    "breeding-price",
+                    false,
+                ),
+                Argument::new_named(
+                    "HATCHING_TIMEOUT",
+                    false,
+                    "Timeout (seconds) before the egg is ready to hatch.",
+                    None,
+                    "hatching-timeout",
+                    false,
+                ),
+                Argument::new_named(
+                    "FORBID_INBREEDING",
+                    false,
+                    "Reject breeding of parents with their children and of siblings.",
+                    None,
+                    "forbid-inbreeding",
+                    false,
+                ),
+                Argument::new_named(
+                    "INBREEDING_MUTATION_RATE",
+                    false,
+                    "Additional mutation rate (per mille) of a completely inbred owl.",
+                    None,
+                    "inbreeding-mutation-rate",
+                    false,
+                ),
+                Argument::new_named(
+                    "AUCTION_EXTENSION_WINDOW",
+                    false,
+                    "Time window (seconds) before the auction end extended by late bids.",
+                    None,
+                    "auction-extension-window",
+                    false,
+                ),
+            ]
+        }
+
+        fn execute(&self, mut context: Context) -> Result<Context, failure::Error> {
+            let default = CryptoOwlsConfig::default();
+            let config = CryptoOwlsConfig {
+                issue_amount: context.arg("ISSUE_AMOUNT").unwrap_or(default.issue_amount),
+                issue_timeout: context
+                    .arg("ISSUE_TIMEOUT")
+                    .unwrap_or(default.issue_timeout),
+                breeding_timeout: context
+                    .arg("BREEDING_TIMEOUT")
+                    .unwrap_or(default.breeding_timeout),
+                breeding_price: context
+                    .arg("BREEDING_PRICE")
+                    .unwrap_or(default.breeding_price),
+                hatching_timeout: context
+                    .arg("HATCHING_TIMEOUT")
+                    .unwrap_or(default.hatching_timeout),
+                forbid_inbreeding: context
+                    .arg("FORBID_INBREEDING")
+                    .unwrap_or(default.forbid_inbreeding),
+                inbreeding_mutation_rate: context
+                    .arg("INBREEDING_MUTATION_RATE")
+                    .unwrap_or(default.inbreeding_mutation_rate),
+                auction_extension_window: context
+                    .arg("AUCTION_EXTENSION_WINDOW")
+                    .unwrap_or(default.auction_extension_window),
+            };
+            config.validate()?;
+
+            let mut values: BTreeMap<String, toml::Value> = context
+                .get(keys::SERVICES_CONFIG)
+                .expect("Expected services_config in context.");
+            values.insert(
+                CRYPTOOWLS_SERVICE_NAME.to_owned(),
+                toml::Value::try_from(config)?,
+            );
+            context.set(keys::SERVICES_CONFIG, values);
+            Ok(context)
+        }
+    }
+
+    /// Passes initial gameplay parameters from the common config to the node config.
+    struct Finalize;
+
+    impl CommandExtension for Finalize {
+        fn args(&self) -> Vec<Argument> {
+            vec![]
+        }
+
+        fn execute(&self, mut context: Context) -> Result<Context, failure::Error> {
+            let mut node_config: NodeConfig = context.get(keys::NODE_CONFIG).unwrap();
+            let common_config = context.get(keys::COMMON_CONFIG).unwrap();
+
+            if let Some(config) = common_config.services_config.get(CRYPTOOWLS_SERVICE_NAME) {
+                parse_config(config)?;
+                node_config
+                    .services_configs
+                    .insert(CRYPTOOWLS_SERVICE_NAME.to_owned(), config.clone());
+            }
+            context.set(keys::NODE_CONFIG, node_config);
+            Ok(context)
         }
     }
 
@@ -1087,31 +5241,85 @@ pub mod service {
             schema.state_hash()
         }
 
+        // Initial service configuration, stored in the consensus configuration.
+        fn initialize(&self, fork: &mut Fork) -> Value {
+            CryptoOwlsSchema::new(fork)
+                .schema_version_mut()
+                .set(SCHEMA_VERSION);
+            serde_json::to_value(&self.config).unwrap()
+        }
+
         // Method to deserialize transactions.
         fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
             let tx = Transactions::tx_from_raw(raw)?;
-            Ok(tx.into())
+            Ok(Box::new(MigratingTransaction(tx.into())))
         }
 
         // Check open auctions state after each block's commit.
         fn before_commit(&self, fork: &mut Fork) {
+            // Migrate the data if the block has no transactions which would do it.
+            CryptoOwlsSchema::new(&mut *fork).migrate();
+
             let current_time = if let Some(time) = transactions::current_time(fork) {
                 time
             } else {
                 return;
             };
 
-            // Check open auctions and close them if time expires.
+            // Hatch eggs once the randomness for them is available. The randomness is taken
+            // from the committed blocks, so only eggs marked in earlier blocks are visited.
+            let height = Schema::new(&*fork).height().next();
+            let ready_eggs = {
+                let schema = CryptoOwlsSchema::new(&*fork);
+                let hatching_eggs = schema.hatching_eggs();
+                hatching_eggs
+                    .iter()
+                    .take_while(|key| key.hatching_height < height.0)
+                    .filter_map(|key| {
+                        self.randomness
+                            .seed(&*fork, Height(key.hatching_height))
+                            .map(|seed| (key.egg_id, seed))
+                    })
+                    .collect::<Vec<_>>()
+            };
+            {
+                let mut schema = CryptoOwlsSchema::new(&mut *fork);
+                for (egg_id, seed) in ready_eggs {
+                    schema.hatch_egg(&egg_id, &seed, current_time);
+                }
+            }
+
+            // Eggs which are ready to hatch wait for the randomness of the current block.
+            // Incubated eggs are ordered by the hatching time, so only due ones are visited.
             let mut schema = CryptoOwlsSchema::new(fork);
-            let open_auctions = schema
-                .owl_auction()
-                .into_iter()
-                .map(|(_, auction_id)| auction_id)
+            let incubated_eggs = schema
+                .incubated_eggs()
+                .iter()
+                .take_while(|key| key.hatch_at <= current_time)
+                .map(|key| key.egg_id)
+                .collect::<Vec<_>>();
+            for egg_id in incubated_eggs {
+                schema.start_hatching(&egg_id, height);
+            }
+
+            // Close auctions (or finish their current phase) if time expires.
+            // Open auctions are ordered by the end time, so only due ones are visited.
+            let due_auctions = schema
+                .auction_expiry()
+                .iter()
+                .take_while(|expiry| expiry.ends_at <= current_time)
+                .map(|expiry| expiry.auction_id)
                 .collect::<Vec<_>>();
-            for auction_id in open_auctions {
+            for auction_id in due_auctions {
                 let auction_state = schema.auctions().get(auction_id).unwrap();
-                if auction_state.ends_at() <= current_time {
-                    schema.close_auction(auction_id);
+                // Sealed bids and the reserve price are revealed before the auction is closed.
+                if auction_state.auction.has_reveal_phase()
+                    && auction_state.phase == AuctionPhase::Bidding
+                {
+                    schema.start_reveal_phase(auction_id);
+                } else if let Err(e) = schema.close_auction(auction_id) {
+                    // The auction stays open and is retried in the next block.
+                    error!("Unable to close auction {}: {}", auction_id, e);
                 }
             }
         }
@@ -12,23 +12,34 @@
 // // See the License for the specific language governing permissions and
 // // limitations under the License.
 
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use serde_json::json;
 
 use exonum::api::node::public::explorer::TransactionResponse;
-use exonum::crypto::{self, PublicKey, SecretKey};
+use exonum::blockchain::{BlockProof, Blockchain};
+use exonum::crypto::{self, CryptoHash, Hash, PublicKey, SecretKey};
 use exonum::helpers::Height;
 use exonum::messages::{to_hex_string, Message, ServiceTransaction};
+use exonum::storage::MapProof;
 use exonum_testkit::{ApiKind, TestKit, TestKitApi, TestKitBuilder};
-use exonum_time::TimeService;
+use exonum_time::{time_provider::MockTimeProvider, TimeService};
 
+use exonum_cryptoowls::api::{
+    AuctionInfo, BidInfo, HistoryInfo, HistoryPage, OwlFamily, OwlInfo, OwlStateInfo, UserInfo,
+    MAX_HISTORY_LIMIT, MAX_PEDIGREE_DEPTH,
+};
+use exonum_cryptoowls::data_layout::{AuctionKind, Egg, EventKind, TraitStats};
+use exonum_cryptoowls::genetics::Traits;
+use exonum_cryptoowls::schema::{
+    CryptoOwlsSchema, AUCTIONS_TABLE_INDEX, OWLS_STATE_TABLE_INDEX, USERS_TABLE_INDEX,
+};
 use exonum_cryptoowls::service::CryptoOwlsService;
 use exonum_cryptoowls::transactions::*;
-use exonum_cryptoowls::CRYPTOOWLS_SERVICE_ID;
+use exonum_cryptoowls::{CRYPTOOWLS_SERVICE_ID, CRYPTOOWLS_SERVICE_NAME, HATCHING_TIMEOUT};
 
 fn init_testkit() -> (TestKit, TestKitApi) {
     let mut testkit = TestKitBuilder::validator()
-        .with_service(CryptoOwlsService)
+        .with_service(CryptoOwlsService::default())
         .with_service(TimeService::default())
         .create();
 
@@ -103,6 +114,13 @@ fn test_tx_create_auction() {
             owl_id: crypto::Hash::zero(),
             start_price: 0,
             duration: 10,
+            cancellable_with_bids: false,
+            buyout_price: 0,
+            kind: AuctionKind::English,
+            reveal_duration: 0,
+            floor_price: 0,
+            decay_interval: 0,
+            reserve_commitment: Hash::zero(),
         },
         keypair.0,
         &keypair.1,
@@ -123,3 +141,689 @@ fn test_tx_make_bid() {
         &keypair.1,
     );
 }
+
+#[test]
+fn test_tx_transfer_owl() {
+    let (_testkit, api) = init_testkit();
+    let keypair = crypto::gen_keypair();
+    post_transaction(
+        &api,
+        TransferOwl {
+            owl_id: crypto::Hash::zero(),
+            to: crypto::gen_keypair().0,
+        },
+        keypair.0,
+        &keypair.1,
+    );
+}
+
+#[test]
+fn test_tx_transfer() {
+    let (_testkit, api) = init_testkit();
+    let keypair = crypto::gen_keypair();
+    post_transaction(
+        &api,
+        Transfer {
+            to: crypto::gen_keypair().0,
+            amount: 1,
+        },
+        keypair.0,
+        &keypair.1,
+    );
+}
+
+#[test]
+fn test_tx_cancel_auction() {
+    let (_testkit, api) = init_testkit();
+    let keypair = crypto::gen_keypair();
+    post_transaction(&api, CancelAuction { auction_id: 0 }, keypair.0, &keypair.1);
+}
+
+#[test]
+fn test_tx_list_owl() {
+    let (_testkit, api) = init_testkit();
+    let keypair = crypto::gen_keypair();
+    post_transaction(
+        &api,
+        ListOwl {
+            owl_id: crypto::Hash::zero(),
+            price: 1,
+        },
+        keypair.0,
+        &keypair.1,
+    );
+}
+
+#[test]
+fn test_tx_buy_owl() {
+    let (_testkit, api) = init_testkit();
+    let keypair = crypto::gen_keypair();
+    post_transaction(&api, BuyOwl { listing_id: 0 }, keypair.0, &keypair.1);
+}
+
+#[test]
+fn test_tx_delist_owl() {
+    let (_testkit, api) = init_testkit();
+    let keypair = crypto::gen_keypair();
+    post_transaction(&api, DelistOwl { listing_id: 0 }, keypair.0, &keypair.1);
+}
+
+#[test]
+fn test_tx_commit_bid() {
+    let (_testkit, api) = init_testkit();
+    let keypair = crypto::gen_keypair();
+    let commitment = sealed_bid_commitment(42, &crypto::hash(b"salt"), &keypair.0);
+    post_transaction(
+        &api,
+        CommitBid {
+            auction_id: 0,
+            commitment,
+            deposit: 50,
+        },
+        keypair.0,
+        &keypair.1,
+    );
+}
+
+#[test]
+fn test_tx_reveal_bid() {
+    let (_testkit, api) = init_testkit();
+    let keypair = crypto::gen_keypair();
+    post_transaction(
+        &api,
+        RevealBid {
+            auction_id: 0,
+            value: 42,
+            salt: crypto::hash(b"salt"),
+        },
+        keypair.0,
+        &keypair.1,
+    );
+}
+
+#[test]
+fn test_tx_offer_stud() {
+    let (_testkit, api) = init_testkit();
+    let keypair = crypto::gen_keypair();
+    post_transaction(
+        &api,
+        OfferStud {
+            owl_id: crypto::hash(&[1, 2, 3]),
+            price: 10,
+        },
+        keypair.0,
+        &keypair.1,
+    );
+}
+
+#[test]
+fn test_tx_withdraw_stud() {
+    let (_testkit, api) = init_testkit();
+    let keypair = crypto::gen_keypair();
+    post_transaction(
+        &api,
+        WithdrawStud {
+            owl_id: crypto::hash(&[1, 2, 3]),
+        },
+        keypair.0,
+        &keypair.1,
+    );
+}
+
+#[test]
+fn test_tx_breed_with_stud() {
+    let (_testkit, api) = init_testkit();
+    let keypair = crypto::gen_keypair();
+    post_transaction(
+        &api,
+        BreedWithStud {
+            name: "Owl".to_owned(),
+            stud_id: crypto::hash(&[1, 2, 3]),
+            mother_id: crypto::hash(&[3, 2, 1]),
+            fee: 10,
+            seed: Utc::now(),
+        },
+        keypair.0,
+        &keypair.1,
+    );
+}
+
+#[test]
+fn test_tx_rename_owl() {
+    let (_testkit, api) = init_testkit();
+    let keypair = crypto::gen_keypair();
+    post_transaction(
+        &api,
+        RenameOwl {
+            owl_id: crypto::hash(&[1, 2, 3]),
+            name: "Owl".to_owned(),
+        },
+        keypair.0,
+        &keypair.1,
+    );
+}
+
+#[test]
+fn test_tx_update_profile() {
+    let (_testkit, api) = init_testkit();
+    let keypair = crypto::gen_keypair();
+    post_transaction(
+        &api,
+        UpdateProfile {
+            name: "Alice".to_owned(),
+        },
+        keypair.0,
+        &keypair.1,
+    );
+}
+
+#[test]
+fn test_auction_price_of_missing_auction() {
+    let (_testkit, api) = init_testkit();
+    let price: Option<u64> = api
+        .public(ApiKind::Service(CRYPTOOWLS_SERVICE_NAME))
+        .query(&json!({ "id": 0 }))
+        .get("v1/auction/price")
+        .unwrap();
+    assert_eq!(price, None);
+}
+
+#[test]
+fn test_eggs_of_missing_user() {
+    let (_testkit, api) = init_testkit();
+    let (pk, _) = crypto::gen_keypair();
+    let eggs: Option<Vec<(Hash, Egg)>> = api
+        .public(ApiKind::Service(CRYPTOOWLS_SERVICE_NAME))
+        .query(&json!({ "pub_key": pk }))
+        .get("v1/user/eggs")
+        .unwrap();
+    assert!(eggs.is_none());
+}
+
+#[test]
+fn test_owl_pedigree() {
+    let time_machine = MockTimeProvider::default();
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CryptoOwlsService::default())
+        .with_service(TimeService::with_provider(time_machine.clone()))
+        .create();
+    testkit.create_blocks_until(Height(2));
+
+    let (pk, sk) = crypto::gen_keypair();
+    testkit.create_block_with_transactions(vec![Message::sign_transaction(
+        CreateUser {
+            name: "Alice".to_owned(),
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        pk,
+        &sk,
+    )]);
+    let parents: Vec<_> = {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        let user_owls = schema.user_owls(&pk);
+        user_owls.iter().map(|(_, id)| id).collect()
+    };
+
+    time_machine.add_time(Duration::seconds(200));
+    testkit.create_blocks_until(Height(6));
+    testkit.create_block_with_transactions(vec![Message::sign_transaction(
+        MakeOwl {
+            name: "Abel".to_owned(),
+            father_id: parents[0],
+            mother_id: parents[1],
+            seed: Utc::now(),
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        pk,
+        &sk,
+    )]);
+    time_machine.add_time(Duration::seconds(HATCHING_TIMEOUT));
+    testkit.create_blocks_until(Height(11));
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let child = schema.owl_children(&parents[0]).iter().next().unwrap().1;
+    let api = testkit.api();
+
+    // Ancestors of the child are proven with the metadata of the family members.
+    let pedigree: Option<OwlFamily> = api
+        .public(ApiKind::Service(CRYPTOOWLS_SERVICE_NAME))
+        .query(&json!({ "id": child, "depth": 1 }))
+        .get("v1/owl/pedigree")
+        .unwrap();
+    let pedigree = pedigree.unwrap();
+    let owls = pedigree.owls.check().unwrap();
+    assert_eq!(owls.merkle_root(), schema.owls_state().merkle_root());
+    assert_eq!(owls.entries().count(), 3);
+    let metadata = pedigree.metadata.check().unwrap();
+    assert_eq!(metadata.merkle_root(), schema.owls_metadata().merkle_root());
+    let (_, child_metadata) = metadata.entries().find(|(id, _)| **id == child).unwrap();
+    assert_eq!(child_metadata.father_id, parents[0]);
+    assert_eq!(child_metadata.mother_id, parents[1]);
+
+    // The parent and its only child.
+    let children: Option<OwlFamily> = api
+        .public(ApiKind::Service(CRYPTOOWLS_SERVICE_NAME))
+        .query(&json!({ "id": parents[0] }))
+        .get("v1/owl/children")
+        .unwrap();
+    let metadata = children.unwrap().metadata.check().unwrap();
+    let mut family: Vec<_> = metadata.entries().map(|(id, _)| *id).collect();
+    family.sort();
+    let mut expected = vec![parents[0], child];
+    expected.sort();
+    assert_eq!(family, expected);
+
+    // Too deep pedigree is rejected.
+    let response = api
+        .public(ApiKind::Service(CRYPTOOWLS_SERVICE_NAME))
+        .query(&json!({ "id": child, "depth": MAX_PEDIGREE_DEPTH + 1 }))
+        .get::<Option<OwlFamily>>("v1/owl/pedigree");
+    assert!(response.is_err());
+}
+
+#[test]
+fn test_owl_traits_and_rarity() {
+    let (mut testkit, _) = init_testkit();
+    let (pk, sk) = crypto::gen_keypair();
+    testkit.create_block_with_transactions(vec![Message::sign_transaction(
+        CreateUser {
+            name: "Alice".to_owned(),
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        pk,
+        &sk,
+    )]);
+    let owl_id = {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        let user_owls = schema.user_owls(&pk);
+        user_owls.iter().next().unwrap().1
+    };
+
+    let owl: Option<OwlInfo> = testkit
+        .api()
+        .public(ApiKind::Service(CRYPTOOWLS_SERVICE_NAME))
+        .query(&json!({ "id": owl_id }))
+        .get("v1/owl")
+        .unwrap();
+    let owl = owl.unwrap();
+    assert_eq!(owl.state.owner, pk);
+    assert_eq!(owl.traits, Traits::from_dna(owl.state.owl.dna));
+
+    let stats: TraitStats = testkit
+        .api()
+        .public(ApiKind::Service(CRYPTOOWLS_SERVICE_NAME))
+        .get("v1/stats/traits")
+        .unwrap();
+    assert_eq!(stats.owls, 2);
+    assert_eq!(owl.rarity, stats.rarity(&owl.traits));
+    assert!(owl.rarity >= 4.0);
+}
+
+#[test]
+fn test_owl_by_name() {
+    let (mut testkit, _) = init_testkit();
+    let (pk, sk) = crypto::gen_keypair();
+    testkit.create_block_with_transactions(vec![Message::sign_transaction(
+        CreateUser {
+            name: "Alice".to_owned(),
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        pk,
+        &sk,
+    )]);
+
+    let api = testkit.api();
+    let owl_by_name = |name: &str| -> Option<Hash> {
+        api.public(ApiKind::Service(CRYPTOOWLS_SERVICE_NAME))
+            .query(&json!({ "name": name }))
+            .get("v1/owl/by-name")
+            .unwrap()
+    };
+    let owl_id = owl_by_name("alice's adam").expect("No owl found by name");
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert_eq!(
+        schema.owls_state().get(&owl_id).unwrap().owl.name,
+        "Alice's Adam"
+    );
+    assert_eq!(owl_by_name("Bob's Adam"), None);
+}
+
+#[test]
+fn test_user_history() {
+    let (mut testkit, _) = init_testkit();
+    let (pk, sk) = crypto::gen_keypair();
+    testkit.create_block_with_transactions(vec![Message::sign_transaction(
+        CreateUser {
+            name: "Alice".to_owned(),
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        pk,
+        &sk,
+    )]);
+
+    let api = testkit.api();
+    let history = |query: serde_json::Value| {
+        api.public(ApiKind::Service(CRYPTOOWLS_SERVICE_NAME))
+            .query(&query)
+            .get::<Option<HistoryPage>>("v1/user/history")
+    };
+
+    let page = history(json!({ "pub_key": pk })).unwrap().unwrap();
+    assert_eq!(page.total, 3);
+    assert_eq!(page.events[0].kind, EventKind::FundsIssued);
+
+    let page = history(json!({ "pub_key": pk, "offset": 1, "limit": 1 }))
+        .unwrap()
+        .unwrap();
+    assert_eq!(page.total, 3);
+    assert_eq!(page.events.len(), 1);
+    assert_eq!(page.events[0].kind, EventKind::OwlBorn);
+
+    let page = history(json!({ "pub_key": pk, "offset": 5 }))
+        .unwrap()
+        .unwrap();
+    assert!(page.events.is_empty());
+
+    assert!(history(json!({ "pub_key": pk, "limit": MAX_HISTORY_LIMIT + 1 })).is_err());
+    assert_eq!(
+        history(json!({ "pub_key": crypto::gen_keypair().0 })).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_user_history_proofs() {
+    let (mut testkit, _) = init_testkit();
+    let keys: Vec<_> = (0..2).map(|_| crypto::gen_keypair()).collect();
+    let users = keys
+        .iter()
+        .zip(&["Alice", "Bob"])
+        .map(|((pk, sk), name)| {
+            Message::sign_transaction(
+                CreateUser {
+                    name: name.to_string(),
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                *pk,
+                sk,
+            )
+        })
+        .collect();
+    testkit.create_block_with_transactions(users);
+    let (alice, bob) = (&keys[0], &keys[1]);
+
+    let api = testkit.api();
+    let history_info = |query: serde_json::Value| -> Option<HistoryInfo> {
+        api.public(ApiKind::Service(CRYPTOOWLS_SERVICE_NAME))
+            .query(&query)
+            .get("v1/user/history/info")
+            .unwrap()
+    };
+
+    let info = history_info(json!({ "pub_key": alice.0 })).unwrap();
+    assert_eq!(info.block_proof.block.height(), testkit.height());
+    let events = info.verify(&alice.0).unwrap();
+    let kinds: Vec<_> = events
+        .iter()
+        .map(|(index, event)| (*index, event.kind))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (0, EventKind::FundsIssued),
+            (1, EventKind::OwlBorn),
+            (2, EventKind::OwlBorn),
+        ]
+    );
+
+    let info = history_info(json!({ "pub_key": alice.0, "offset": 1, "limit": 1 })).unwrap();
+    let events = info.verify(&alice.0).unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!((events[0].0, events[0].1.kind), (1, EventKind::OwlBorn));
+
+    // The history of another user does not match the proven history root.
+    let mut info = history_info(json!({ "pub_key": alice.0 })).unwrap();
+    assert!(history_info(json!({ "pub_key": bob.0 }))
+        .unwrap()
+        .verify(&alice.0)
+        .is_err());
+    info.history_proof.to_events = history_info(json!({ "pub_key": bob.0 }))
+        .unwrap()
+        .history_proof
+        .to_events;
+    assert!(info.verify(&alice.0).is_err());
+
+    assert!(history_info(json!({ "pub_key": alice.0, "offset": 3 })).is_none());
+    assert!(history_info(json!({ "pub_key": crypto::gen_keypair().0 })).is_none());
+}
+
+/// Checks the proof of the service table against the block, as a light client would,
+/// and returns the proven root of the table.
+fn check_table_proof(block_proof: &BlockProof, to_table: MapProof<Hash, Hash>, idx: usize) -> Hash {
+    let block = &block_proof.block;
+    assert!(!block_proof.precommits.is_empty());
+    for precommit in &block_proof.precommits {
+        assert_eq!(*precommit.block_hash(), block.hash());
+    }
+
+    let to_table = to_table.check().unwrap();
+    assert_eq!(to_table.merkle_root(), *block.state_hash());
+    let key = Blockchain::service_table_unique_key(CRYPTOOWLS_SERVICE_ID, idx);
+    let (_, table_root) = to_table
+        .entries()
+        .find(|(table_key, _)| **table_key == key)
+        .expect("No table root in the proof");
+    *table_root
+}
+
+#[test]
+fn test_proofs() {
+    let (mut testkit, _) = init_testkit();
+    let (pk, sk) = crypto::gen_keypair();
+    testkit.create_block_with_transactions(vec![Message::sign_transaction(
+        CreateUser {
+            name: "Alice".to_owned(),
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        pk,
+        &sk,
+    )]);
+    let owl_id = {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        let owl_id = schema.user_owls(&pk).iter().next().unwrap().1;
+        owl_id
+    };
+    let reserve_commitment = reserve_price_commitment(5, &crypto::hash(b"salt"), &pk);
+    testkit.create_block_with_transactions(vec![Message::sign_transaction(
+        CreateAuction {
+            owl_id,
+            start_price: 10,
+            duration: 100,
+            cancellable_with_bids: false,
+            buyout_price: 0,
+            kind: AuctionKind::English,
+            reveal_duration: 10,
+            floor_price: 0,
+            decay_interval: 0,
+            reserve_commitment,
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        pk,
+        &sk,
+    )]);
+
+    let api = testkit.api();
+    let service_api = api.public(ApiKind::Service(CRYPTOOWLS_SERVICE_NAME));
+
+    // User.
+    let info: UserInfo = service_api
+        .query(&json!({ "pub_key": pk }))
+        .get("v1/user/info")
+        .unwrap();
+    assert_eq!(info.block_proof.block.height(), testkit.height());
+    let table_root = check_table_proof(
+        &info.block_proof,
+        info.user_proof.to_table,
+        USERS_TABLE_INDEX,
+    );
+    let to_user = info.user_proof.to_user.check().unwrap();
+    assert_eq!(to_user.merkle_root(), table_root);
+    let users: Vec<_> = to_user.entries().collect();
+    assert_eq!(users.len(), 1);
+    assert_eq!((*users[0].0, users[0].1.name.as_str()), (pk, "Alice"));
+
+    // Absence of the user is proven as well.
+    let stranger = crypto::gen_keypair().0;
+    let info: UserInfo = service_api
+        .query(&json!({ "pub_key": stranger }))
+        .get("v1/user/info")
+        .unwrap();
+    let table_root = check_table_proof(
+        &info.block_proof,
+        info.user_proof.to_table,
+        USERS_TABLE_INDEX,
+    );
+    let to_user = info.user_proof.to_user.check().unwrap();
+    assert_eq!(to_user.merkle_root(), table_root);
+    assert_eq!(
+        to_user.all_entries().collect::<Vec<_>>(),
+        vec![(&stranger, None)]
+    );
+
+    // Owl and its owner.
+    let info: OwlStateInfo = service_api
+        .query(&json!({ "id": owl_id }))
+        .get("v1/owl/info")
+        .unwrap();
+    let table_root = check_table_proof(
+        &info.block_proof,
+        info.owl_proof.to_table,
+        OWLS_STATE_TABLE_INDEX,
+    );
+    let to_owl = info.owl_proof.to_owl.check().unwrap();
+    assert_eq!(to_owl.merkle_root(), table_root);
+    let owls: Vec<_> = to_owl.entries().collect();
+    assert_eq!(owls.len(), 1);
+    assert_eq!((*owls[0].0, owls[0].1.owner), (owl_id, pk));
+
+    // Auction.
+    let info: Option<AuctionInfo> = service_api
+        .query(&json!({ "id": 0 }))
+        .get("v1/auction/info")
+        .unwrap();
+    let info = info.expect("No auction info");
+    let table_root = check_table_proof(
+        &info.block_proof,
+        info.auction_proof.to_table,
+        AUCTIONS_TABLE_INDEX,
+    );
+    let auctions = info
+        .auction_proof
+        .to_auction
+        .validate(table_root, info.auction_proof.auctions_count)
+        .unwrap();
+    assert_eq!(auctions.len(), 1);
+    assert_eq!(auctions[0].0, 0);
+    assert_eq!(auctions[0].1.auction.owl_id, owl_id);
+    // Only the commitment to the reserve price is public until the reveal.
+    assert_eq!(auctions[0].1.auction.reserve_price, 0);
+    assert_eq!(auctions[0].1.auction.reserve_commitment, reserve_commitment);
+
+    let info: Option<AuctionInfo> = service_api
+        .query(&json!({ "id": 1 }))
+        .get("v1/auction/info")
+        .unwrap();
+    assert!(info.is_none());
+}
+
+#[test]
+fn test_bid_proofs() {
+    let (mut testkit, _) = init_testkit();
+    let keys: Vec<_> = (0..3).map(|_| crypto::gen_keypair()).collect();
+    let users = keys
+        .iter()
+        .zip(&["Alice", "Bob", "Jane"])
+        .map(|((pk, sk), name)| {
+            Message::sign_transaction(
+                CreateUser {
+                    name: (*name).to_owned(),
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                *pk,
+                sk,
+            )
+        })
+        .collect();
+    testkit.create_block_with_transactions(users);
+    let (alice, bob, jane) = (&keys[0], &keys[1], &keys[2]);
+
+    let owls: Vec<Hash> = {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        let owls = schema.user_owls(&alice.0).iter().map(|x| x.1).collect();
+        owls
+    };
+    let auctions = owls
+        .iter()
+        .map(|owl_id| {
+            Message::sign_transaction(
+                CreateAuction {
+                    owl_id: *owl_id,
+                    start_price: 10,
+                    duration: 1_000,
+                    cancellable_with_bids: false,
+                    buyout_price: 0,
+                    kind: AuctionKind::English,
+                    reveal_duration: 0,
+                    floor_price: 0,
+                    decay_interval: 0,
+                    reserve_commitment: Hash::zero(),
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                alice.0,
+                &alice.1,
+            )
+        })
+        .collect();
+    testkit.create_block_with_transactions(auctions);
+    for &(auction_id, bidder, value) in &[(0, bob, 20), (0, jane, 30), (1, bob, 15)] {
+        testkit.create_block_with_transactions(vec![Message::sign_transaction(
+            MakeBid { auction_id, value },
+            CRYPTOOWLS_SERVICE_ID,
+            bidder.0,
+            &bidder.1,
+        )]);
+    }
+
+    let api = testkit.api();
+    let bid_info = |query: serde_json::Value| -> Option<BidInfo> {
+        api.public(ApiKind::Service(CRYPTOOWLS_SERVICE_NAME))
+            .query(&query)
+            .get("v1/auction/bid/info")
+            .unwrap()
+    };
+
+    let info = bid_info(json!({ "auction_id": 0, "index": 0 })).unwrap();
+    assert_eq!(info.block_proof.block.height(), testkit.height());
+    let (auction_state, index, bid) = info.verify().unwrap();
+    assert_eq!(auction_state.auction.owl_id, owls[0]);
+    assert_eq!((index, bid.public_key, bid.value), (0, bob.0, 20));
+
+    // The last bid by default.
+    let info = bid_info(json!({ "auction_id": 0 })).unwrap();
+    let (_, index, bid) = info.verify().unwrap();
+    assert_eq!((index, bid.public_key, bid.value), (1, jane.0, 30));
+
+    // The bid of another auction does not match the bidding root.
+    let mut info = bid_info(json!({ "auction_id": 0 })).unwrap();
+    info.bid_proof = bid_info(json!({ "auction_id": 1 })).unwrap().bid_proof;
+    assert!(info.verify().is_err());
+
+    assert!(bid_info(json!({ "auction_id": 0, "index": 2 })).is_none());
+    assert!(bid_info(json!({ "auction_id": 2 })).is_none());
+}
@@ -0,0 +1,341 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Property tests for conservation of user funds across random transaction sequences.
+
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+use exonum::blockchain::TransactionErrorType;
+use exonum::crypto::{self, Hash, PublicKey, SecretKey};
+use exonum::helpers::Height;
+use exonum::messages::{Message, RawTransaction, Signed};
+use exonum_testkit::{TestKit, TestKitBuilder};
+use exonum_time::{time_provider::MockTimeProvider, TimeService};
+
+use exonum_cryptoowls::config::CryptoOwlsConfig;
+use exonum_cryptoowls::data_layout::AuctionKind;
+use exonum_cryptoowls::schema::CryptoOwlsSchema;
+use exonum_cryptoowls::service::CryptoOwlsService;
+use exonum_cryptoowls::transactions::*;
+use exonum_cryptoowls::{CRYPTOOWLS_SERVICE_ID, ISSUE_AMOUNT};
+
+const SEEDS: u32 = 20;
+const STEPS: usize = 40;
+
+fn init_testkit(config: CryptoOwlsConfig) -> (TestKit, MockTimeProvider) {
+    let mock_provider = MockTimeProvider::default();
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CryptoOwlsService::new(config))
+        .with_service(TimeService::with_provider(mock_provider.clone()))
+        .create();
+    testkit.create_blocks_until(Height(2));
+    (testkit, mock_provider)
+}
+
+fn create_users(testkit: &mut TestKit, count: usize) -> Vec<(PublicKey, SecretKey)> {
+    let keys: Vec<_> = (0..count).map(|_| crypto::gen_keypair()).collect();
+    let txs: Vec<_> = keys
+        .iter()
+        .enumerate()
+        .map(|(i, (pk, sk))| {
+            Message::sign_transaction(
+                CreateUser {
+                    name: format!("User {}", i),
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                *pk,
+                sk,
+            )
+        })
+        .collect();
+    testkit
+        .create_block_with_transactions(txs)
+        .transactions
+        .into_iter()
+        .for_each(|tx| tx.status().unwrap());
+    keys
+}
+
+/// Total funds of all users.
+fn total_supply(testkit: &TestKit) -> u64 {
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let mut supply = 0;
+    for user in schema.users().values() {
+        supply += user.balance + user.reserved;
+    }
+    supply
+}
+
+/// Checks that reserved funds of each user are equal to their top bids in open auctions.
+fn check_reserves(testkit: &TestKit) {
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let mut expected = HashMap::new();
+    for (_, auction_id) in schema.owl_auction().iter() {
+        if let Some(bid) = schema.auction_bids(auction_id).last() {
+            *expected.entry(bid.public_key).or_insert(0) += bid.value;
+        }
+    }
+    for user in schema.users().values() {
+        assert_eq!(
+            user.reserved,
+            expected.get(&user.public_key).cloned().unwrap_or(0)
+        );
+    }
+}
+
+/// Random transaction of a random user and the amount of funds it issues.
+fn random_transaction<R: Rng>(
+    rng: &mut R,
+    testkit: &TestKit,
+    keys: &[(PublicKey, SecretKey)],
+) -> (Signed<RawTransaction>, u64) {
+    let (pk, sk) = &keys[rng.gen_range(0, keys.len())];
+    let (to, _) = &keys[rng.gen_range(0, keys.len())];
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let auctions = schema.auctions().len();
+    let listings = schema.listings().len();
+    let owls = schema
+        .user_owls(pk)
+        .iter()
+        .map(|(_, owl_id)| owl_id)
+        .collect::<Vec<_>>();
+    // User may have sold all the owls.
+    let owl_id = if owls.is_empty() {
+        crypto::hash(b"missing owl")
+    } else {
+        owls[rng.gen_range(0, owls.len())]
+    };
+    let amount = rng.gen_range(0, 2 * ISSUE_AMOUNT);
+
+    let tx = match rng.gen_range(0, 9) {
+        0 => {
+            let tx = Message::sign_transaction(
+                Issue { seed: Utc::now() },
+                CRYPTOOWLS_SERVICE_ID,
+                *pk,
+                sk,
+            );
+            return (tx, ISSUE_AMOUNT);
+        }
+        1 => {
+            Message::sign_transaction(Transfer { to: *to, amount }, CRYPTOOWLS_SERVICE_ID, *pk, sk)
+        }
+        2 => Message::sign_transaction(
+            CreateAuction {
+                owl_id,
+                start_price: amount / 2,
+                duration: rng.gen_range(1, 200),
+                cancellable_with_bids: rng.gen(),
+                buyout_price: if rng.gen() { amount * 2 + 1 } else { 0 },
+                kind: AuctionKind::English,
+                reveal_duration: rng.gen_range(1, 100),
+                floor_price: 0,
+                decay_interval: 0,
+                reserve_commitment: if rng.gen() {
+                    reserve_price_commitment(ISSUE_AMOUNT, &crypto::hash(pk.as_ref()), pk)
+                } else {
+                    Hash::zero()
+                },
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            *pk,
+            sk,
+        ),
+        3 | 4 => Message::sign_transaction(
+            MakeBid {
+                auction_id: rng.gen_range(0, auctions + 1),
+                value: amount,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            *pk,
+            sk,
+        ),
+        5 => Message::sign_transaction(
+            CancelAuction {
+                auction_id: rng.gen_range(0, auctions + 1),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            *pk,
+            sk,
+        ),
+        6 => Message::sign_transaction(
+            RevealReserve {
+                auction_id: rng.gen_range(0, auctions + 1),
+                reserve_price: ISSUE_AMOUNT,
+                salt: crypto::hash(pk.as_ref()),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            *pk,
+            sk,
+        ),
+        7 => Message::sign_transaction(
+            ListOwl {
+                owl_id,
+                price: amount,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            *pk,
+            sk,
+        ),
+        _ => Message::sign_transaction(
+            BuyOwl {
+                listing_id: rng.gen_range(0, listings + 1),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            *pk,
+            sk,
+        ),
+    };
+    (tx, 0)
+}
+
+#[test]
+fn test_funds_conservation() {
+    for seed in 0..SEEDS {
+        let mut rng = XorShiftRng::from_seed([0x0f, 0x1e, 0x2d, seed + 1]);
+        let (mut testkit, time_machine) = init_testkit(CryptoOwlsConfig {
+            issue_timeout: 0,
+            ..CryptoOwlsConfig::default()
+        });
+        let keys = create_users(&mut testkit, 4);
+        let mut supply = total_supply(&testkit);
+
+        for _ in 0..STEPS {
+            let (txs, issues): (Vec<_>, Vec<_>) = (0..rng.gen_range(1, 5))
+                .map(|_| random_transaction(&mut rng, &testkit, &keys))
+                .unzip();
+
+            let block = testkit.create_block_with_transactions(txs);
+            for (tx, issued) in block.transactions.iter().zip(issues) {
+                match tx.status() {
+                    Ok(()) => supply += issued,
+                    Err(e) => match e.error_type() {
+                        TransactionErrorType::Code(_) => {}
+                        other => panic!("Transaction panicked: {:?}", other),
+                    },
+                }
+            }
+            assert_eq!(total_supply(&testkit), supply, "seed {}", seed);
+            check_reserves(&testkit);
+
+            // Let some auctions expire.
+            if rng.gen_weighted_bool(3) {
+                time_machine.add_time(Duration::seconds(rng.gen_range(1, 100)));
+                let height = testkit.height();
+                testkit.create_blocks_until(height.next().next());
+                assert_eq!(total_supply(&testkit), supply, "seed {}", seed);
+                check_reserves(&testkit);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_balance_overflow() {
+    let (mut testkit, _) = init_testkit(CryptoOwlsConfig {
+        issue_amount: u64::max_value(),
+        ..CryptoOwlsConfig::default()
+    });
+    let keys = create_users(&mut testkit, 2);
+    let (alice, bob) = (&keys[0], &keys[1]);
+
+    let block = testkit.create_block_with_transactions(vec![Message::sign_transaction(
+        Transfer {
+            to: alice.0,
+            amount: 1,
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        bob.0,
+        &bob.1,
+    )]);
+    assert_eq!(
+        block.transactions[0].status().map_err(|e| e.error_type()),
+        Err(TransactionErrorType::Code(
+            ErrorKind::BalanceOverflow.as_code()
+        ))
+    );
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert_eq!(
+        schema.users().get(&alice.0).unwrap().balance,
+        u64::max_value()
+    );
+    assert_eq!(
+        schema.users().get(&bob.0).unwrap().balance,
+        u64::max_value()
+    );
+}
+
+#[test]
+fn test_stud_fee_overflow() {
+    let (mut testkit, time_machine) = init_testkit(CryptoOwlsConfig::default());
+    let keys = create_users(&mut testkit, 2);
+    let (alice, bob) = (&keys[0], &keys[1]);
+    let (stud, mother) = {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        let first_owl = |pk: &PublicKey| schema.user_owls(pk).iter().next().unwrap().1;
+        (first_owl(&alice.0), first_owl(&bob.0))
+    };
+
+    time_machine.add_time(Duration::seconds(200));
+    let height = testkit.height();
+    testkit.create_blocks_until(height.next().next());
+
+    let block = testkit.create_block_with_transactions(vec![
+        Message::sign_transaction(
+            OfferStud {
+                owl_id: stud,
+                price: u64::max_value(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice.0,
+            &alice.1,
+        ),
+        Message::sign_transaction(
+            BreedWithStud {
+                name: "Foal".to_owned(),
+                stud_id: stud,
+                mother_id: mother,
+                fee: u64::max_value(),
+                seed: Utc::now(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            bob.0,
+            &bob.1,
+        ),
+    ]);
+    // The breeding price and the fee can not be charged together.
+    assert_eq!(
+        block
+            .transactions
+            .iter()
+            .map(|tx| tx.status().map_err(|e| e.error_type()))
+            .collect::<Vec<_>>(),
+        vec![
+            Ok(()),
+            Err(TransactionErrorType::Code(
+                ErrorKind::BalanceOverflow.as_code()
+            )),
+        ]
+    );
+}
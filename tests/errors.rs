@@ -0,0 +1,302 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transactions sent from an unregistered key or without the time oracle
+//! should fail instead of panicking.
+
+use chrono::Utc;
+
+use exonum::blockchain::TransactionErrorType;
+use exonum::crypto::{self, Hash, PublicKey, SecretKey};
+use exonum::helpers::Height;
+use exonum::messages::{Message, RawTransaction, Signed};
+use exonum_testkit::{TestKit, TestKitBuilder};
+use exonum_time::{time_provider::MockTimeProvider, TimeService};
+
+use exonum_cryptoowls::data_layout::AuctionKind;
+use exonum_cryptoowls::service::CryptoOwlsService;
+use exonum_cryptoowls::transactions::*;
+use exonum_cryptoowls::CRYPTOOWLS_SERVICE_ID;
+
+/// Transactions of every type signed with the given keys, except `CreateUser`.
+fn all_transactions(pk: PublicKey, sk: &SecretKey) -> Vec<(&'static str, Signed<RawTransaction>)> {
+    let owl_id = crypto::hash(b"owl");
+    vec![
+        (
+            "MakeOwl",
+            Message::sign_transaction(
+                MakeOwl {
+                    name: "Owl".to_owned(),
+                    father_id: owl_id,
+                    mother_id: Hash::zero(),
+                    seed: Utc::now(),
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                pk,
+                sk,
+            ),
+        ),
+        (
+            "Issue",
+            Message::sign_transaction(Issue { seed: Utc::now() }, CRYPTOOWLS_SERVICE_ID, pk, sk),
+        ),
+        (
+            "CreateAuction",
+            Message::sign_transaction(
+                CreateAuction {
+                    owl_id,
+                    start_price: 10,
+                    duration: 100,
+                    cancellable_with_bids: false,
+                    buyout_price: 0,
+                    kind: AuctionKind::English,
+                    reveal_duration: 0,
+                    floor_price: 0,
+                    decay_interval: 0,
+                    reserve_commitment: Hash::zero(),
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                pk,
+                sk,
+            ),
+        ),
+        (
+            "MakeBid",
+            Message::sign_transaction(
+                MakeBid {
+                    auction_id: 0,
+                    value: 10,
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                pk,
+                sk,
+            ),
+        ),
+        (
+            "TransferOwl",
+            Message::sign_transaction(
+                TransferOwl {
+                    owl_id,
+                    to: crypto::gen_keypair().0,
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                pk,
+                sk,
+            ),
+        ),
+        (
+            "Transfer",
+            Message::sign_transaction(
+                Transfer {
+                    to: crypto::gen_keypair().0,
+                    amount: 10,
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                pk,
+                sk,
+            ),
+        ),
+        (
+            "CancelAuction",
+            Message::sign_transaction(
+                CancelAuction { auction_id: 0 },
+                CRYPTOOWLS_SERVICE_ID,
+                pk,
+                sk,
+            ),
+        ),
+        (
+            "ListOwl",
+            Message::sign_transaction(ListOwl { owl_id, price: 10 }, CRYPTOOWLS_SERVICE_ID, pk, sk),
+        ),
+        (
+            "BuyOwl",
+            Message::sign_transaction(BuyOwl { listing_id: 0 }, CRYPTOOWLS_SERVICE_ID, pk, sk),
+        ),
+        (
+            "DelistOwl",
+            Message::sign_transaction(DelistOwl { listing_id: 0 }, CRYPTOOWLS_SERVICE_ID, pk, sk),
+        ),
+        (
+            "CommitBid",
+            Message::sign_transaction(
+                CommitBid {
+                    auction_id: 0,
+                    commitment: Hash::zero(),
+                    deposit: 10,
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                pk,
+                sk,
+            ),
+        ),
+        (
+            "RevealBid",
+            Message::sign_transaction(
+                RevealBid {
+                    auction_id: 0,
+                    value: 10,
+                    salt: Hash::zero(),
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                pk,
+                sk,
+            ),
+        ),
+        (
+            "RevealReserve",
+            Message::sign_transaction(
+                RevealReserve {
+                    auction_id: 0,
+                    reserve_price: 10,
+                    salt: Hash::zero(),
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                pk,
+                sk,
+            ),
+        ),
+        (
+            "OfferStud",
+            Message::sign_transaction(
+                OfferStud { owl_id, price: 10 },
+                CRYPTOOWLS_SERVICE_ID,
+                pk,
+                sk,
+            ),
+        ),
+        (
+            "WithdrawStud",
+            Message::sign_transaction(WithdrawStud { owl_id }, CRYPTOOWLS_SERVICE_ID, pk, sk),
+        ),
+        (
+            "BreedWithStud",
+            Message::sign_transaction(
+                BreedWithStud {
+                    name: "Owl".to_owned(),
+                    stud_id: owl_id,
+                    mother_id: Hash::zero(),
+                    fee: 10,
+                    seed: Utc::now(),
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                pk,
+                sk,
+            ),
+        ),
+        (
+            "RenameOwl",
+            Message::sign_transaction(
+                RenameOwl {
+                    owl_id,
+                    name: "Owl".to_owned(),
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                pk,
+                sk,
+            ),
+        ),
+        (
+            "UpdateProfile",
+            Message::sign_transaction(
+                UpdateProfile {
+                    name: "Alice".to_owned(),
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                pk,
+                sk,
+            ),
+        ),
+    ]
+}
+
+/// Executes transactions in a single block and returns their error codes.
+fn execute(
+    testkit: &mut TestKit,
+    transactions: Vec<(&'static str, Signed<RawTransaction>)>,
+) -> Vec<(&'static str, Option<u8>)> {
+    let (names, transactions): (Vec<_>, Vec<_>) = transactions.into_iter().unzip();
+    let block = testkit.create_block_with_transactions(transactions);
+    names
+        .into_iter()
+        .zip(block.transactions.iter())
+        .map(|(name, tx)| {
+            let code = tx.status().err().map(|e| match e.error_type() {
+                TransactionErrorType::Code(code) => code,
+                other => panic!("{} failed with {:?}", name, other),
+            });
+            (name, code)
+        })
+        .collect()
+}
+
+#[test]
+fn test_unregistered_user() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CryptoOwlsService::default())
+        .with_service(TimeService::with_provider(MockTimeProvider::default()))
+        .create();
+    testkit.create_blocks_until(Height(2));
+
+    let (pk, sk) = crypto::gen_keypair();
+    for (name, code) in execute(&mut testkit, all_transactions(pk, &sk)) {
+        assert_eq!(
+            code,
+            Some(ErrorKind::UserIsNotRegistered.as_code()),
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_time_not_available() {
+    // Without the time service the time oracle never has a value.
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CryptoOwlsService::default())
+        .create();
+
+    let (pk, sk) = crypto::gen_keypair();
+    let mut transactions = all_transactions(pk, &sk);
+    transactions.push((
+        "CreateUser",
+        Message::sign_transaction(
+            CreateUser {
+                name: "Alice".to_owned(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            pk,
+            &sk,
+        ),
+    ));
+
+    // Transactions which do not depend on time fail because the user is not registered.
+    let timeless = [
+        "TransferOwl",
+        "Transfer",
+        "BuyOwl",
+        "DelistOwl",
+        "WithdrawStud",
+        "RenameOwl",
+        "UpdateProfile",
+    ];
+    for (name, code) in execute(&mut testkit, transactions) {
+        let expected = if timeless.contains(&name) {
+            ErrorKind::UserIsNotRegistered
+        } else {
+            ErrorKind::TimeNotAvailable
+        };
+        assert_eq!(code, Some(expected.as_code()), "{}", name);
+    }
+}
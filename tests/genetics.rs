@@ -0,0 +1,83 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for DNA decoding. Test vectors are generated from `splitDNA` in the frontend
+//! by `npm run split-dna-vectors` in the `frontend` directory.
+
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use exonum_cryptoowls::data_layout::CryptoOwl;
+use exonum_cryptoowls::genetics::{Color, Traits, BODY_PART_VARIANTS, DNA_FIELD_TAG};
+
+#[test]
+fn test_split_dna_vectors() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/split_dna.json");
+    let vectors: Vec<Value> = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    assert!(!vectors.is_empty());
+    for vector in vectors {
+        let dna = vector["dna"].as_u64().unwrap() as u32;
+        let split = &vector["split"];
+        let appearance = &split["appearance"];
+        let traits = Traits::from_dna(dna);
+        assert_eq!(
+            traits.color.to_hex(),
+            split["color"].as_str().unwrap(),
+            "color of {:#010x}",
+            dna
+        );
+        assert_eq!(
+            (traits.eyes, traits.wings, traits.chest, traits.tail),
+            (
+                appearance["eyes"].as_u64().unwrap() as u8,
+                appearance["wings"].as_u64().unwrap() as u8,
+                appearance["chest"].as_u64().unwrap() as u8,
+                appearance["tail"].as_u64().unwrap() as u8,
+            ),
+            "appearance of {:#010x}",
+            dna
+        );
+        assert_eq!(Traits::from_dna(traits.to_dna()), traits);
+    }
+}
+
+#[test]
+fn test_traits_round_trip() {
+    for appearance in 0..=255u32 {
+        let dna = 0xff00_0000 | appearance << 16 | 0xcdef;
+        let traits = Traits::from_dna(dna);
+        for part in &[traits.eyes, traits.wings, traits.chest, traits.tail] {
+            assert!(*part < BODY_PART_VARIANTS);
+        }
+        assert_eq!(traits.to_dna(), dna & 0x00ff_ffff);
+    }
+
+    let owl = CryptoOwl {
+        name: "Owl".to_owned(),
+        dna: 0x1b33_2211,
+    };
+    let traits = Traits::from(&owl);
+    assert_eq!(
+        traits.color,
+        Color {
+            red: DNA_FIELD_TAG,
+            green: 0x11,
+            blue: 0x22,
+        }
+    );
+    assert_eq!(traits.eyes, 0);
+    assert_eq!(traits.tail, 3);
+}
@@ -0,0 +1,63 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Golden-file tests for the owl renderer. Fixtures are `OwlIcon.vue` mounted in jsdom
+//! by `npm run owl-fixtures` in the `frontend` directory, which also generates the template
+//! of the renderer (`src/owl.svg`).
+
+use std::fs;
+use std::path::Path;
+
+use exonum::api;
+use exonum::crypto;
+use exonum::helpers::Height;
+use exonum_testkit::{ApiKind, TestKitBuilder};
+use serde_json::json;
+
+use exonum_cryptoowls::render::owl_svg;
+use exonum_cryptoowls::service::CryptoOwlsService;
+use exonum_cryptoowls::CRYPTOOWLS_SERVICE_NAME;
+
+#[test]
+fn test_golden_images() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/owls");
+    let mut count = 0;
+    for entry in fs::read_dir(fixtures).unwrap() {
+        let path = entry.unwrap().path();
+        let dna = path.file_stem().unwrap().to_str().unwrap();
+        let dna = u32::from_str_radix(dna, 16).unwrap();
+        let expected = fs::read_to_string(&path).unwrap();
+        assert_eq!(owl_svg(dna), expected, "image of {:#010x}", dna);
+        count += 1;
+    }
+    assert!(count > 0);
+}
+
+#[test]
+fn test_image_of_missing_owl() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CryptoOwlsService::default())
+        .create();
+    testkit.create_blocks_until(Height(1));
+
+    let response = testkit
+        .api()
+        .public(ApiKind::Service(CRYPTOOWLS_SERVICE_NAME))
+        .query(&json!({ "id": crypto::hash(b"owl") }))
+        .get::<String>("v1/owl/image");
+    match response {
+        Err(api::Error::NotFound(_)) => {}
+        other => panic!("Unexpected response: {:?}", other),
+    }
+}
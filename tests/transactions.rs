@@ -12,24 +12,39 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
 
 use chrono::{Duration, Utc};
+use protobuf::{CodedOutputStream, ProtobufResult};
+use serde_json::json;
 
-use exonum::crypto::{self, CryptoHash};
+use exonum::blockchain::{Schema as CoreSchema, TransactionErrorType};
+use exonum::crypto::{self, CryptoHash, Hash, PublicKey, SecretKey};
 use exonum::helpers::Height;
 use exonum::messages::Message;
+use exonum::storage::{ProofListIndex, Snapshot};
 use exonum_testkit::{txvec, TestKit, TestKitBuilder};
 use exonum_time::{time_provider::MockTimeProvider, TimeService};
 
+use exonum_cryptoowls::config::CryptoOwlsConfig;
+use exonum_cryptoowls::data_layout::{
+    AuctionExpiry, AuctionKind, AuctionPhase, AuctionState, CloseReason, Egg, EggHatching,
+    EggIncubation, EventKind, TraitStats, UserEvent,
+};
+use exonum_cryptoowls::genetics::Traits;
+use exonum_cryptoowls::randomness::RandomnessSource;
 use exonum_cryptoowls::schema::CryptoOwlsSchema;
 use exonum_cryptoowls::service::CryptoOwlsService;
 use exonum_cryptoowls::transactions::*;
-use exonum_cryptoowls::{CRYPTOOWLS_SERVICE_ID, ISSUE_AMOUNT};
+use exonum_cryptoowls::{
+    CRYPTOOWLS_SERVICE_ID, CRYPTOOWLS_SERVICE_NAME, HATCHING_TIMEOUT, ISSUE_AMOUNT, MUTATION_RATE,
+    SCHEMA_VERSION,
+};
 
 fn init_testkit() -> (TestKit, MockTimeProvider) {
     let mock_provider = MockTimeProvider::default();
     let mut testkit = TestKitBuilder::validator()
-        .with_service(CryptoOwlsService)
+        .with_service(CryptoOwlsService::default())
         .with_service(TimeService::with_provider(mock_provider.clone()))
         .create();
 
@@ -39,6 +54,10 @@ fn init_testkit() -> (TestKit, MockTimeProvider) {
     (testkit, mock_provider)
 }
 
+fn error_code(kind: ErrorKind) -> TransactionErrorType {
+    TransactionErrorType::Code(kind.as_code())
+}
+
 #[test]
 fn test_create_user() {
     let (mut testkit, _) = init_testkit();
@@ -70,6 +89,11 @@ fn test_create_user() {
     for state in owl_states {
         assert!(user_owls.contains(&state.owl.hash()));
         assert_eq!(state.owner, pk);
+
+        let metadata = schema.owls_metadata().get(&state.owl.hash()).unwrap();
+        assert_eq!(metadata.father_id, Hash::zero());
+        assert_eq!(metadata.mother_id, Hash::zero());
+        assert_eq!(metadata.generation, 0);
     }
 
     assert_eq!(user.public_key, pk);
@@ -185,6 +209,14 @@ fn test_breeding() {
         &sk
     )]);
 
+    // The son is still an egg
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert_eq!(schema.user_owls(&pk).iter().count(), 2);
+    assert_eq!(schema.user_eggs(&pk).iter().count(), 1);
+
+    hatch_eggs(&mut testkit, &time_machine);
+
     let snapshot = testkit.snapshot();
     let schema = CryptoOwlsSchema::new(&snapshot);
     let user_owls_idx = schema.user_owls(&pk);
@@ -192,6 +224,7 @@ fn test_breeding() {
 
     let user_owls_count = user_owls_idx.iter().count();
     assert_eq!(user_owls_count, 3);
+    assert_eq!(schema.user_eggs(&pk).iter().count(), 0);
 
     let new_owl_states: Vec<_> = owls_idx.iter().map(|x| x.1).collect();
 
@@ -232,6 +265,651 @@ fn test_breeding() {
     assert_eq!(user_owls_count, 3);
 }
 
+/// Lets eggs laid so far become ready to hatch and waits for their hatching.
+fn hatch_eggs(testkit: &mut TestKit, time_machine: &MockTimeProvider) {
+    time_machine.add_time(Duration::seconds(HATCHING_TIMEOUT));
+    let height = testkit.height();
+    testkit.create_blocks_until(Height(height.0 + 3));
+}
+
+/// Randomness source which reveals the seed only when the test allows it.
+#[derive(Debug, Clone, Default)]
+struct ManualRandomness(Arc<RwLock<Option<Hash>>>);
+
+impl RandomnessSource for ManualRandomness {
+    fn seed(&self, _snapshot: &dyn Snapshot, _laid_at: Height) -> Option<Hash> {
+        *self.0.read().unwrap()
+    }
+}
+
+#[test]
+fn test_egg_hatching() {
+    let mock_provider = MockTimeProvider::default();
+    let randomness = ManualRandomness::default();
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CryptoOwlsService::default().with_randomness(randomness.clone()))
+        .with_service(TimeService::with_provider(mock_provider.clone()))
+        .create();
+    testkit.create_blocks_until(Height(2));
+
+    let (pk, sk) = crypto::gen_keypair();
+    testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        CreateUser {
+            name: "Alice".to_owned(),
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        pk,
+        &sk
+    )]);
+    let parents = user_owls(&testkit, &pk);
+
+    mock_provider.add_time(Duration::seconds(200));
+    testkit.create_blocks_until(Height(8));
+
+    let breed = Message::sign_transaction(
+        MakeOwl {
+            name: "Abel".to_owned(),
+            father_id: parents[0],
+            mother_id: parents[1],
+            seed: Utc::now(),
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        pk,
+        &sk,
+    );
+    let egg_id = breed.hash();
+    testkit.create_block_with_transactions(txvec![breed]);
+
+    // The egg is not ready to hatch until the hatching timeout expires.
+    testkit.create_blocks_until(Height(12));
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let egg = schema.eggs().get(&egg_id).expect("No egg persisted");
+    assert_eq!(egg.owner, pk);
+    assert_eq!(egg.name, "Abel");
+    assert_eq!(egg.laid_at, 9);
+    assert_eq!(
+        egg.hatch_at,
+        egg.bred_at + Duration::seconds(HATCHING_TIMEOUT)
+    );
+    assert_eq!(egg.hatching_height, 0);
+    assert_eq!(
+        schema.incubated_eggs().iter().collect::<Vec<_>>(),
+        vec![EggIncubation {
+            hatch_at: egg.hatch_at,
+            egg_id,
+        }]
+    );
+    assert!(schema.hatching_eggs().iter().next().is_none());
+
+    // The egg waits until the randomness is available.
+    hatch_eggs(&mut testkit, &mock_provider);
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let egg = schema
+        .eggs()
+        .get(&egg_id)
+        .expect("Egg is hatched without randomness");
+    assert_ne!(egg.hatching_height, 0);
+    assert_eq!(user_owls(&testkit, &pk).len(), 2);
+    assert!(schema.incubated_eggs().iter().next().is_none());
+    assert_eq!(
+        schema.hatching_eggs().iter().collect::<Vec<_>>(),
+        vec![EggHatching {
+            hatching_height: egg.hatching_height,
+            egg_id,
+        }]
+    );
+
+    // The child DNA is derived from the revealed seed and the egg identifier.
+    let seed = crypto::hash(b"revealed seed");
+    let expected = schema.make_uniq_owl(
+        (egg.father_dna, egg.mother_dna),
+        &egg.name,
+        &crypto::hash(&[seed.as_ref(), egg_id.as_ref()].concat()),
+        MUTATION_RATE,
+    );
+    *randomness.0.write().unwrap() = Some(seed);
+    testkit.create_block();
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert!(schema.eggs().get(&egg_id).is_none());
+    assert!(schema.hatching_eggs().iter().next().is_none());
+    let child = schema
+        .owls_state()
+        .get(&expected.hash())
+        .expect("Egg is not hatched");
+    assert_eq!(child.owl, expected);
+    assert_eq!(child.owner, pk);
+    assert_eq!(child.last_breeding, egg.bred_at);
+    assert_eq!(user_owls(&testkit, &pk).len(), 3);
+
+    let metadata = schema.owls_metadata().get(&expected.hash()).unwrap();
+    assert_eq!(metadata.father_id, parents[0]);
+    assert_eq!(metadata.mother_id, parents[1]);
+    assert_eq!(metadata.generation, 1);
+    assert!(metadata.born_at >= egg.hatch_at);
+    for parent_id in &parents {
+        assert!(schema.owl_children(parent_id).contains(&expected.hash()));
+    }
+}
+
+#[test]
+fn test_inbreeding() {
+    for &forbid_inbreeding in &[true, false] {
+        let time_machine = MockTimeProvider::default();
+        let config = CryptoOwlsConfig {
+            forbid_inbreeding,
+            inbreeding_mutation_rate: 300,
+            ..CryptoOwlsConfig::default()
+        };
+        let mut testkit = TestKitBuilder::validator()
+            .with_service(CryptoOwlsService::new(config))
+            .with_service(TimeService::with_provider(time_machine.clone()))
+            .create();
+        testkit.create_blocks_until(Height(2));
+
+        let keys = create_users(&mut testkit, &["Alice"]);
+        let (pk, sk) = &keys[0];
+        let parents = user_owls(&testkit, pk);
+
+        time_machine.add_time(Duration::seconds(200));
+        let height = testkit.height();
+        testkit.create_blocks_until(height.next().next());
+        testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+            MakeOwl {
+                name: "Abel".to_owned(),
+                father_id: parents[0],
+                mother_id: parents[1],
+                seed: Utc::now(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            *pk,
+            sk,
+        )]);
+        hatch_eggs(&mut testkit, &time_machine);
+
+        let child = {
+            let snapshot = testkit.snapshot();
+            let schema = CryptoOwlsSchema::new(&snapshot);
+            let children = schema.owl_children(&parents[0]);
+            let child = children.iter().next().unwrap().1;
+            assert_eq!(schema.owls_metadata().get(&child).unwrap().inbreeding, 0);
+            child
+        };
+
+        // Breeding of the child with its parent.
+        time_machine.add_time(Duration::seconds(200));
+        let height = testkit.height();
+        testkit.create_blocks_until(height.next().next());
+        let breed = Message::sign_transaction(
+            MakeOwl {
+                name: "Cain".to_owned(),
+                father_id: child,
+                mother_id: parents[0],
+                seed: Utc::now(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            *pk,
+            sk,
+        );
+        let egg_id = breed.hash();
+        let block = testkit.create_block_with_transactions(txvec![breed]);
+
+        let status = block.transactions[0].status().map_err(|e| e.error_type());
+        if forbid_inbreeding {
+            assert_eq!(status, Err(error_code(ErrorKind::Inbreeding)));
+            continue;
+        }
+        assert_eq!(status, Ok(()));
+
+        // The only common ancestor is the parent itself: F = 1/2^(1 + 0 + 1).
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        assert_eq!(schema.eggs().get(&egg_id).unwrap().inbreeding, 250);
+        assert_eq!(schema.inbreeding_coefficient(&parents[0], &parents[1]), 0);
+
+        hatch_eggs(&mut testkit, &time_machine);
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        let children = schema.owl_children(&child);
+        let grandchild = children.iter().next().unwrap().1;
+        let metadata = schema.owls_metadata().get(&grandchild).unwrap();
+        assert_eq!(metadata.generation, 2);
+        assert_eq!(metadata.inbreeding, 250);
+    }
+}
+
+#[test]
+fn test_inbreeding_of_siblings() {
+    let (mut testkit, _) = init_testkit();
+    let mut fork = testkit.blockchain_mut().fork();
+    let mut schema = CryptoOwlsSchema::new(&mut fork);
+
+    // Two known generations: unrelated grandparents of both lines
+    // and the parents of the siblings.
+    let owl = |name: &str| crypto::hash(name.as_bytes());
+    let born_at = Utc::now();
+    for &(child, father, mother) in &[
+        ("Father", "Grandfather 1", "Grandmother 1"),
+        ("Mother", "Grandfather 2", "Grandmother 2"),
+        ("Brother", "Father", "Mother"),
+        ("Sister", "Father", "Mother"),
+    ] {
+        schema.record_birth(&owl(child), &owl(father), &owl(mother), 0, born_at);
+    }
+
+    // Common ancestors are the parents: F = 2 * 1/2^(1 + 1 + 1). Paths to the grandparents
+    // go through the parents, so they are not counted again.
+    assert_eq!(
+        schema.inbreeding_coefficient(&owl("Brother"), &owl("Sister")),
+        250
+    );
+    // The only common ancestor is the parent itself: F = 1/2^(1 + 0 + 1).
+    assert_eq!(
+        schema.inbreeding_coefficient(&owl("Brother"), &owl("Mother")),
+        250
+    );
+    assert_eq!(
+        schema.inbreeding_coefficient(&owl("Father"), &owl("Mother")),
+        0
+    );
+}
+
+#[test]
+fn test_stud_breeding() {
+    let (mut testkit, time_machine) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob", "Jane"]);
+    let (alice, bob, jane) = (&keys[0], &keys[1], &keys[2]);
+    let stud = user_owls(&testkit, &alice.0)[0];
+    let mother = user_owls(&testkit, &bob.0)[0];
+    let fee = 15;
+
+    time_machine.add_time(Duration::seconds(200));
+    let height = testkit.height();
+    testkit.create_blocks_until(height.next().next());
+
+    testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        OfferStud {
+            owl_id: stud,
+            price: fee,
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        alice.0,
+        &alice.1,
+    )]);
+
+    let breed = |keys: &(PublicKey, SecretKey), mother_id: Hash, fee: u64| {
+        Message::sign_transaction(
+            BreedWithStud {
+                name: "Foal".to_owned(),
+                stud_id: stud,
+                mother_id,
+                fee,
+                seed: Utc::now(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            keys.0,
+            &keys.1,
+        )
+    };
+    let valid_breeding = breed(bob, mother, fee);
+    let egg_id = valid_breeding.hash();
+    let alice_owl = user_owls(&testkit, &alice.0)[1];
+    let block = testkit.create_block_with_transactions(txvec![
+        // The fee is changed by the owner.
+        breed(bob, mother, fee - 1),
+        // Own owls are bred without the offer.
+        breed(alice, alice_owl, fee),
+        // Bob does not own the mother.
+        breed(bob, alice_owl, fee),
+        valid_breeding,
+        // The stud has just been bred.
+        breed(jane, user_owls(&testkit, &jane.0)[0], fee),
+    ]);
+
+    let statuses: Vec<_> = block
+        .transactions
+        .iter()
+        .map(|tx| tx.status().map_err(|e| e.error_type()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            Err(error_code(ErrorKind::StudFeeMismatch)),
+            Err(error_code(ErrorKind::NoSelfBuying)),
+            Err(error_code(ErrorKind::AccessViolation)),
+            Ok(()),
+            Err(error_code(ErrorKind::EarlyBreeding)),
+        ]
+    );
+
+    {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        let egg = schema.eggs().get(&egg_id).unwrap();
+        assert_eq!(egg.owner, bob.0);
+        assert_eq!(egg.father_id, stud);
+        assert_eq!(egg.mother_id, mother);
+
+        // The stud stays with the owner, and the offer stays active.
+        let stud_state = schema.owls_state().get(&stud).unwrap();
+        assert_eq!(stud_state.owner, alice.0);
+        assert_eq!(stud_state.last_breeding, egg.bred_at);
+        assert!(schema.stud_offers().contains(&stud));
+
+        let config = CryptoOwlsConfig::default();
+        assert_eq!(
+            schema.users().get(&alice.0).unwrap().balance,
+            ISSUE_AMOUNT + fee
+        );
+        assert_eq!(
+            schema.users().get(&bob.0).unwrap().balance,
+            ISSUE_AMOUNT - fee - config.breeding_price
+        );
+    }
+
+    // Only the owner can withdraw the offer.
+    let block = testkit.create_block_with_transactions(txvec![
+        Message::sign_transaction(
+            WithdrawStud { owl_id: stud },
+            CRYPTOOWLS_SERVICE_ID,
+            bob.0,
+            &bob.1,
+        ),
+        Message::sign_transaction(
+            WithdrawStud { owl_id: stud },
+            CRYPTOOWLS_SERVICE_ID,
+            alice.0,
+            &alice.1,
+        ),
+    ]);
+    assert_eq!(
+        block.transactions[0].status().map_err(|e| e.error_type()),
+        Err(error_code(ErrorKind::AccessViolation))
+    );
+    block.transactions[1].status().unwrap();
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert!(!schema.stud_offers().contains(&stud));
+    assert_eq!(schema.user_stud_offers(&alice.0).iter().count(), 0);
+}
+
+#[test]
+fn test_stud_offer_follows_owner() {
+    let (mut testkit, _) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob"]);
+    let (alice, bob) = (&keys[0], &keys[1]);
+    let stud = user_owls(&testkit, &alice.0)[0];
+
+    testkit.create_block_with_transactions(txvec![
+        Message::sign_transaction(
+            OfferStud {
+                owl_id: stud,
+                price: 10,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice.0,
+            &alice.1,
+        ),
+        Message::sign_transaction(
+            TransferOwl {
+                owl_id: stud,
+                to: bob.0,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice.0,
+            &alice.1,
+        ),
+    ]);
+
+    // The offer of the previous owner is withdrawn.
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert!(!schema.stud_offers().contains(&stud));
+    assert!(!schema.user_stud_offers(&alice.0).contains(&stud));
+}
+
+#[test]
+fn test_stud_on_sale() {
+    let (mut testkit, time_machine) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob"]);
+    let (alice, bob) = (&keys[0], &keys[1]);
+    let alice_owls = user_owls(&testkit, &alice.0);
+    let mother = user_owls(&testkit, &bob.0)[0];
+
+    time_machine.add_time(Duration::seconds(200));
+    let height = testkit.height();
+    testkit.create_blocks_until(height.next().next());
+
+    let offer = |owl_id: Hash| {
+        Message::sign_transaction(
+            OfferStud { owl_id, price: 10 },
+            CRYPTOOWLS_SERVICE_ID,
+            alice.0,
+            &alice.1,
+        )
+    };
+    let block = testkit.create_block_with_transactions(txvec![
+        offer(alice_owls[0]),
+        Message::sign_transaction(
+            ListOwl {
+                owl_id: alice_owls[1],
+                price: 10,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice.0,
+            &alice.1,
+        ),
+        // Listed owls can not be offered.
+        offer(alice_owls[1]),
+    ]);
+    assert_eq!(
+        block.transactions[2].status().map_err(|e| e.error_type()),
+        Err(error_code(ErrorKind::OwlAlreadyListed))
+    );
+
+    // Auctioned owls can not be offered, and the offered stud can not be bred
+    // while it is auctioned.
+    start_auction(&mut testkit, alice, alice_owls[0], false);
+    let block = testkit.create_block_with_transactions(txvec![
+        offer(alice_owls[0]),
+        Message::sign_transaction(
+            BreedWithStud {
+                name: "Foal".to_owned(),
+                stud_id: alice_owls[0],
+                mother_id: mother,
+                fee: 10,
+                seed: Utc::now(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            bob.0,
+            &bob.1,
+        ),
+    ]);
+    let statuses: Vec<_> = block
+        .transactions
+        .iter()
+        .map(|tx| tx.status().map_err(|e| e.error_type()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            Err(error_code(ErrorKind::OwlAlreadyAuctioned)),
+            Err(error_code(ErrorKind::OwlAlreadyAuctioned)),
+        ]
+    );
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert_eq!(schema.eggs().keys().count(), 0);
+    assert_eq!(schema.user_eggs(&bob.0).iter().count(), 0);
+}
+
+#[test]
+fn test_stale_stud_offer() {
+    let (mut testkit, time_machine) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob", "Jane"]);
+    let (alice, bob, jane) = (&keys[0], &keys[1], &keys[2]);
+    let stud = user_owls(&testkit, &alice.0)[0];
+    let mother = user_owls(&testkit, &bob.0)[0];
+
+    time_machine.add_time(Duration::seconds(200));
+    let height = testkit.height();
+    testkit.create_blocks_until(height.next().next());
+
+    testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        OfferStud {
+            owl_id: stud,
+            price: 10,
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        alice.0,
+        &alice.1,
+    )]);
+
+    // Simulate the stud which changed hands without its offer being withdrawn.
+    let mut fork = testkit.blockchain_mut().fork();
+    {
+        let mut schema = CryptoOwlsSchema::new(&mut fork);
+        let mut stud_state = schema.owls_state().get(&stud).unwrap();
+        stud_state.owner = jane.0;
+        schema.owls_state_mut().put(&stud, stud_state);
+    }
+    testkit.blockchain_mut().merge(fork.into_patch()).unwrap();
+
+    let block = testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        BreedWithStud {
+            name: "Foal".to_owned(),
+            stud_id: stud,
+            mother_id: mother,
+            fee: 10,
+            seed: Utc::now(),
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        bob.0,
+        &bob.1,
+    )]);
+    assert_eq!(
+        block.transactions[0].status().map_err(|e| e.error_type()),
+        Err(error_code(ErrorKind::StudOfferNotFound))
+    );
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert_eq!(schema.eggs().keys().count(), 0);
+    assert_eq!(schema.users().get(&alice.0).unwrap().balance, ISSUE_AMOUNT);
+    assert_eq!(schema.users().get(&jane.0).unwrap().balance, ISSUE_AMOUNT);
+}
+
+#[test]
+fn test_egg_trading() {
+    let (mut testkit, time_machine) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob", "Jane"]);
+    let (alice, bob, jane) = (&keys[0], &keys[1], &keys[2]);
+    let parents = user_owls(&testkit, &alice.0);
+
+    time_machine.add_time(Duration::seconds(200));
+    let height = testkit.height();
+    testkit.create_blocks_until(height.next().next());
+
+    let breed = Message::sign_transaction(
+        MakeOwl {
+            name: "Abel".to_owned(),
+            father_id: parents[0],
+            mother_id: parents[1],
+            seed: Utc::now(),
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        alice.0,
+        &alice.1,
+    );
+    let egg_id = breed.hash();
+    testkit.create_block_with_transactions(txvec![breed]);
+
+    // Alice gives the egg to Bob.
+    testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        TransferOwl {
+            owl_id: egg_id,
+            to: bob.0,
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        alice.0,
+        &alice.1,
+    )]);
+
+    {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        assert_eq!(schema.eggs().get(&egg_id).unwrap().owner, bob.0);
+        assert!(!schema.user_eggs(&alice.0).contains(&egg_id));
+        assert!(schema.user_eggs(&bob.0).contains(&egg_id));
+    }
+
+    // Bob sells the egg.
+    let price = 10;
+    testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        ListOwl {
+            owl_id: egg_id,
+            price,
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        bob.0,
+        &bob.1,
+    )]);
+    testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        BuyOwl { listing_id: 0 },
+        CRYPTOOWLS_SERVICE_ID,
+        jane.0,
+        &jane.1,
+    )]);
+
+    {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        assert_eq!(schema.eggs().get(&egg_id).unwrap().owner, jane.0);
+        assert!(schema.user_eggs(&jane.0).contains(&egg_id));
+        assert_eq!(
+            schema.users().get(&jane.0).unwrap().balance,
+            ISSUE_AMOUNT - price
+        );
+        assert_eq!(
+            schema.users().get(&bob.0).unwrap().balance,
+            ISSUE_AMOUNT + price
+        );
+    }
+
+    // Jane lists the egg again, and it stays on sale after hatching.
+    testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        ListOwl {
+            owl_id: egg_id,
+            price,
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        jane.0,
+        &jane.1,
+    )]);
+    hatch_eggs(&mut testkit, &time_machine);
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert!(schema.eggs().get(&egg_id).is_none());
+    assert_eq!(schema.user_eggs(&jane.0).iter().count(), 0);
+
+    let owl_id = schema.listings().get(1).unwrap().listing.owl_id;
+    let owl_state = schema
+        .owls_state()
+        .get(&owl_id)
+        .expect("Egg is not hatched");
+    assert_eq!(owl_state.owl.name, "Abel");
+    assert_eq!(owl_state.owner, jane.0);
+    assert!(schema.user_owls(&jane.0).contains(&owl_id));
+    assert_eq!(schema.owl_listing().get(&owl_id), Some(1));
+    assert!(!schema.owl_listing().contains(&egg_id));
+}
+
 #[test]
 fn test_sell_owl() {
     let (mut testkit, time_machine) = init_testkit();
@@ -279,6 +957,13 @@ fn test_sell_owl() {
                 owl_id: alice_owl,
                 start_price: 10,
                 duration: 1_000,
+                cancellable_with_bids: false,
+                buyout_price: 0,
+                kind: AuctionKind::English,
+                reveal_duration: 0,
+                floor_price: 0,
+                decay_interval: 0,
+                reserve_commitment: Hash::zero(),
             },
             CRYPTOOWLS_SERVICE_ID,
             alice_keys.0,
@@ -354,6 +1039,7 @@ fn test_sell_owl() {
         assert_eq!(alice.reserved, 0);
 
         assert!(auction.closed);
+        assert_eq!(auction.close_reason, CloseReason::Sold);
         assert!(schema.owl_auction().get(&alice_owl).is_none());
         assert!(schema.user_owls(&jane_keys.0).contains_by_hash(&alice_owl));
         assert!(!schema.user_owls(&alice_keys.0).contains_by_hash(&alice_owl));
@@ -401,6 +1087,13 @@ fn test_two_bids_same_user() {
                 owl_id: bob_owl,
                 start_price: 10,
                 duration: 1_000,
+                cancellable_with_bids: false,
+                buyout_price: 0,
+                kind: AuctionKind::English,
+                reveal_duration: 0,
+                floor_price: 0,
+                decay_interval: 0,
+                reserve_commitment: Hash::zero(),
             },
             CRYPTOOWLS_SERVICE_ID,
             bob_keys.0,
@@ -445,3 +1138,2252 @@ fn test_two_bids_same_user() {
         assert_eq!(jane.reserved, 30);
     }
 }
+
+#[test]
+fn test_transfer_owl() {
+    let (mut testkit, _) = init_testkit();
+    let alice_keys = crypto::gen_keypair();
+    let bob_keys = crypto::gen_keypair();
+    let stranger_keys = crypto::gen_keypair();
+
+    testkit.create_block_with_transactions(txvec![
+        Message::sign_transaction(
+            CreateUser {
+                name: "Alice".to_owned(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+        Message::sign_transaction(
+            CreateUser {
+                name: "Bob".to_owned(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            bob_keys.0,
+            &bob_keys.1,
+        ),
+    ]);
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let alice_owls: Vec<_> = schema
+        .user_owls(&alice_keys.0)
+        .iter()
+        .map(|x| x.1)
+        .collect();
+    let (gift, auctioned) = (alice_owls[0], alice_owls[1]);
+
+    // Put the second owl on auction.
+    testkit
+        .create_block_with_transactions(txvec![Message::sign_transaction(
+            CreateAuction {
+                owl_id: auctioned,
+                start_price: 10,
+                duration: 1_000,
+                cancellable_with_bids: false,
+                buyout_price: 0,
+                kind: AuctionKind::English,
+                reveal_duration: 0,
+                floor_price: 0,
+                decay_interval: 0,
+                reserve_commitment: Hash::zero(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1
+        )])
+        .transactions
+        .into_iter()
+        .for_each(|tx| tx.status().unwrap());
+
+    let block = testkit.create_block_with_transactions(txvec![
+        // Unknown receiver.
+        Message::sign_transaction(
+            TransferOwl {
+                owl_id: gift,
+                to: stranger_keys.0,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+        // Self transfer.
+        Message::sign_transaction(
+            TransferOwl {
+                owl_id: gift,
+                to: alice_keys.0,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+        // Owl is not owned by the author.
+        Message::sign_transaction(
+            TransferOwl {
+                owl_id: gift,
+                to: alice_keys.0,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            bob_keys.0,
+            &bob_keys.1,
+        ),
+        // Owl is auctioned.
+        Message::sign_transaction(
+            TransferOwl {
+                owl_id: auctioned,
+                to: bob_keys.0,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+        // Valid transfer.
+        Message::sign_transaction(
+            TransferOwl {
+                owl_id: gift,
+                to: bob_keys.0,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+    ]);
+
+    let statuses: Vec<_> = block
+        .transactions
+        .iter()
+        .map(|tx| tx.status().map_err(|e| e.error_type()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            Err(error_code(ErrorKind::ReceiverIsNotRegistered)),
+            Err(error_code(ErrorKind::SelfTransfer)),
+            Err(error_code(ErrorKind::OwlNotOwned)),
+            Err(error_code(ErrorKind::OwlAlreadyAuctioned)),
+            Ok(()),
+        ]
+    );
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+
+    assert_eq!(schema.owls_state().get(&gift).unwrap().owner, bob_keys.0);
+    assert!(schema.user_owls(&bob_keys.0).contains(&gift));
+    assert!(!schema.user_owls(&alice_keys.0).contains(&gift));
+    assert_eq!(schema.user_owls(&bob_keys.0).iter().count(), 3);
+    assert_eq!(schema.user_owls(&alice_keys.0).iter().count(), 1);
+}
+
+fn total_supply(testkit: &TestKit) -> u64 {
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let mut supply = 0;
+    for user in schema.users().values() {
+        supply += user.balance + user.reserved;
+    }
+    supply
+}
+
+fn create_users(testkit: &mut TestKit, names: &[&str]) -> Vec<(PublicKey, SecretKey)> {
+    let keys: Vec<_> = names.iter().map(|_| crypto::gen_keypair()).collect();
+    let txs: Vec<_> = names
+        .iter()
+        .zip(&keys)
+        .map(|(name, (pk, sk))| {
+            Message::sign_transaction(
+                CreateUser {
+                    name: (*name).to_owned(),
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                *pk,
+                sk,
+            )
+        })
+        .collect();
+    testkit
+        .create_block_with_transactions(txs)
+        .transactions
+        .into_iter()
+        .for_each(|tx| tx.status().unwrap());
+    keys
+}
+
+#[test]
+fn test_transfer_funds() {
+    let (mut testkit, _) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob"]);
+    let (alice_keys, bob_keys) = (&keys[0], &keys[1]);
+    let supply = total_supply(&testkit);
+
+    testkit
+        .create_block_with_transactions(txvec![Message::sign_transaction(
+            Transfer {
+                to: bob_keys.0,
+                amount: 40,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1
+        )])
+        .transactions
+        .into_iter()
+        .for_each(|tx| tx.status().unwrap());
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let alice = schema.users().get(&alice_keys.0).unwrap();
+    let bob = schema.users().get(&bob_keys.0).unwrap();
+
+    assert_eq!(alice.balance, ISSUE_AMOUNT - 40);
+    assert_eq!(bob.balance, ISSUE_AMOUNT + 40);
+    assert_eq!(total_supply(&testkit), supply);
+}
+
+#[test]
+fn test_transfer_invalid_receiver() {
+    let (mut testkit, _) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice"]);
+    let alice_keys = &keys[0];
+    let stranger_keys = crypto::gen_keypair();
+    let supply = total_supply(&testkit);
+
+    let block = testkit.create_block_with_transactions(txvec![
+        Message::sign_transaction(
+            Transfer {
+                to: stranger_keys.0,
+                amount: 10,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+        Message::sign_transaction(
+            Transfer {
+                to: alice_keys.0,
+                amount: 10,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+        Message::sign_transaction(
+            Transfer {
+                to: alice_keys.0,
+                amount: 10,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            stranger_keys.0,
+            &stranger_keys.1,
+        ),
+    ]);
+
+    let statuses: Vec<_> = block
+        .transactions
+        .iter()
+        .map(|tx| tx.status().map_err(|e| e.error_type()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            Err(error_code(ErrorKind::ReceiverIsNotRegistered)),
+            Err(error_code(ErrorKind::SelfTransfer)),
+            Err(error_code(ErrorKind::UserIsNotRegistered)),
+        ]
+    );
+    assert_eq!(total_supply(&testkit), supply);
+}
+
+#[test]
+fn test_transfer_respects_reserved_funds() {
+    let (mut testkit, _) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob"]);
+    let (alice_keys, bob_keys) = (&keys[0], &keys[1]);
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let alice_owls = schema.user_owls(&alice_keys.0);
+    let alice_owl = alice_owls.iter().map(|x| x.1).next().unwrap();
+
+    // Bob reserves 80 in Alice's auction.
+    testkit
+        .create_block_with_transactions(txvec![Message::sign_transaction(
+            CreateAuction {
+                owl_id: alice_owl,
+                start_price: 10,
+                duration: 1_000,
+                cancellable_with_bids: false,
+                buyout_price: 0,
+                kind: AuctionKind::English,
+                reveal_duration: 0,
+                floor_price: 0,
+                decay_interval: 0,
+                reserve_commitment: Hash::zero(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1
+        )])
+        .transactions
+        .into_iter()
+        .for_each(|tx| tx.status().unwrap());
+    testkit
+        .create_block_with_transactions(txvec![Message::sign_transaction(
+            MakeBid {
+                auction_id: 0,
+                value: 80,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            bob_keys.0,
+            &bob_keys.1
+        )])
+        .transactions
+        .into_iter()
+        .for_each(|tx| tx.status().unwrap());
+    let supply = total_supply(&testkit);
+
+    let block = testkit.create_block_with_transactions(txvec![
+        Message::sign_transaction(
+            Transfer {
+                to: alice_keys.0,
+                amount: 50,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            bob_keys.0,
+            &bob_keys.1,
+        ),
+        Message::sign_transaction(
+            Transfer {
+                to: alice_keys.0,
+                amount: 20,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            bob_keys.0,
+            &bob_keys.1,
+        ),
+    ]);
+
+    let statuses: Vec<_> = block
+        .transactions
+        .iter()
+        .map(|tx| tx.status().map_err(|e| e.error_type()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![Err(error_code(ErrorKind::InsufficientFunds)), Ok(())]
+    );
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let alice = schema.users().get(&alice_keys.0).unwrap();
+    let bob = schema.users().get(&bob_keys.0).unwrap();
+
+    assert_eq!(bob.balance, 0);
+    assert_eq!(bob.reserved, 80);
+    assert_eq!(alice.balance, ISSUE_AMOUNT + 20);
+    assert_eq!(total_supply(&testkit), supply);
+}
+
+fn user_owls(testkit: &TestKit, pk: &PublicKey) -> Vec<Hash> {
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let owls: Vec<_> = schema.user_owls(pk).iter().map(|x| x.1).collect();
+    owls
+}
+
+fn start_auction(
+    testkit: &mut TestKit,
+    seller: &(PublicKey, SecretKey),
+    owl_id: Hash,
+    cancellable_with_bids: bool,
+) {
+    testkit
+        .create_block_with_transactions(txvec![Message::sign_transaction(
+            CreateAuction {
+                owl_id,
+                start_price: 10,
+                duration: 1_000,
+                cancellable_with_bids,
+                buyout_price: 0,
+                kind: AuctionKind::English,
+                reveal_duration: 0,
+                floor_price: 0,
+                decay_interval: 0,
+                reserve_commitment: Hash::zero(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            seller.0,
+            &seller.1
+        )])
+        .transactions
+        .into_iter()
+        .for_each(|tx| tx.status().unwrap());
+}
+
+#[test]
+fn test_cancel_auction_without_bids() {
+    let (mut testkit, _) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob"]);
+    let (alice_keys, bob_keys) = (&keys[0], &keys[1]);
+    let alice_owl = user_owls(&testkit, &alice_keys.0)[0];
+
+    start_auction(&mut testkit, alice_keys, alice_owl, false);
+
+    let block = testkit.create_block_with_transactions(txvec![
+        // Only the seller can cancel the auction.
+        Message::sign_transaction(
+            CancelAuction { auction_id: 0 },
+            CRYPTOOWLS_SERVICE_ID,
+            bob_keys.0,
+            &bob_keys.1,
+        ),
+        Message::sign_transaction(
+            CancelAuction { auction_id: 0 },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+        // Auction is already closed.
+        Message::sign_transaction(
+            CancelAuction { auction_id: 0 },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+    ]);
+
+    let statuses: Vec<_> = block
+        .transactions
+        .iter()
+        .map(|tx| tx.status().map_err(|e| e.error_type()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            Err(error_code(ErrorKind::AccessViolation)),
+            Ok(()),
+            Err(error_code(ErrorKind::AuctionClosed)),
+        ]
+    );
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let auction = schema.auctions().get(0).unwrap();
+
+    assert!(auction.closed);
+    assert_eq!(auction.close_reason, CloseReason::Cancelled);
+    assert!(schema.owl_auction().get(&alice_owl).is_none());
+    assert_eq!(
+        schema.owls_state().get(&alice_owl).unwrap().owner,
+        alice_keys.0
+    );
+}
+
+#[test]
+fn test_cancel_auction_with_bids() {
+    let (mut testkit, _) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob"]);
+    let (alice_keys, bob_keys) = (&keys[0], &keys[1]);
+    let alice_owls = user_owls(&testkit, &alice_keys.0);
+    let supply = total_supply(&testkit);
+
+    // The first auction protects bidders, the second one may be cancelled at any time.
+    start_auction(&mut testkit, alice_keys, alice_owls[0], false);
+    start_auction(&mut testkit, alice_keys, alice_owls[1], true);
+    testkit
+        .create_block_with_transactions(txvec![
+            Message::sign_transaction(
+                MakeBid {
+                    auction_id: 0,
+                    value: 20,
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                bob_keys.0,
+                &bob_keys.1,
+            ),
+            Message::sign_transaction(
+                MakeBid {
+                    auction_id: 1,
+                    value: 30,
+                },
+                CRYPTOOWLS_SERVICE_ID,
+                bob_keys.0,
+                &bob_keys.1,
+            ),
+        ])
+        .transactions
+        .into_iter()
+        .for_each(|tx| tx.status().unwrap());
+
+    let block = testkit.create_block_with_transactions(txvec![
+        Message::sign_transaction(
+            CancelAuction { auction_id: 0 },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+        Message::sign_transaction(
+            CancelAuction { auction_id: 1 },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+    ]);
+
+    let statuses: Vec<_> = block
+        .transactions
+        .iter()
+        .map(|tx| tx.status().map_err(|e| e.error_type()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![Err(error_code(ErrorKind::AuctionHasBids)), Ok(())]
+    );
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let protected = schema.auctions().get(0).unwrap();
+    let cancelled = schema.auctions().get(1).unwrap();
+    let bob = schema.users().get(&bob_keys.0).unwrap();
+
+    assert!(!protected.closed);
+    assert_eq!(protected.close_reason, CloseReason::Open);
+    assert!(cancelled.closed);
+    assert_eq!(cancelled.close_reason, CloseReason::Cancelled);
+
+    // Only the bid in the cancelled auction is refunded.
+    assert_eq!(bob.balance, ISSUE_AMOUNT - 20);
+    assert_eq!(bob.reserved, 20);
+    assert_eq!(
+        schema.owls_state().get(&alice_owls[1]).unwrap().owner,
+        alice_keys.0
+    );
+    assert_eq!(total_supply(&testkit), supply);
+}
+
+#[test]
+fn test_cancel_finished_auction() {
+    let (mut testkit, time_machine) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob"]);
+    let (alice_keys, bob_keys) = (&keys[0], &keys[1]);
+    let alice_owl = user_owls(&testkit, &alice_keys.0)[0];
+
+    start_auction(&mut testkit, alice_keys, alice_owl, true);
+    make_bid(&mut testkit, bob_keys, 20);
+
+    // The next time transaction moves the time past the end of the auction.
+    time_machine.add_time(Duration::seconds(1_001));
+    testkit.create_block();
+    testkit.poll_events();
+    let time_tx = {
+        let snapshot = testkit.snapshot();
+        let pool = CoreSchema::new(&snapshot).transactions_pool();
+        let mut pool = pool.iter();
+        let time_tx = pool.next().expect("No time transaction in the pool");
+        assert!(pool.next().is_none());
+        time_tx
+    };
+
+    // The auction is finished, but it is closed only when the block is committed.
+    let cancel = Message::sign_transaction(
+        CancelAuction { auction_id: 0 },
+        CRYPTOOWLS_SERVICE_ID,
+        alice_keys.0,
+        &alice_keys.1,
+    );
+    let cancel_hash = cancel.hash();
+    testkit.add_tx(cancel);
+    let block = testkit.create_block_with_tx_hashes(&[time_tx, cancel_hash]);
+    let statuses: Vec<_> = block
+        .transactions
+        .iter()
+        .map(|tx| tx.status().map_err(|e| e.error_type()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![Ok(()), Err(error_code(ErrorKind::AuctionClosed))]
+    );
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let auction = schema.auctions().get(0).unwrap();
+    assert_eq!(auction.close_reason, CloseReason::Sold);
+    assert_eq!(
+        schema.owls_state().get(&alice_owl).unwrap().owner,
+        jane_keys.0
+    );
+}
+
+#[test]
+fn test_fixed_price_listing() {
+    let (mut testkit, _) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob"]);
+    let (alice_keys, bob_keys) = (&keys[0], &keys[1]);
+    let alice_owls = user_owls(&testkit, &alice_keys.0);
+    let supply = total_supply(&testkit);
+
+    let block = testkit.create_block_with_transactions(txvec![
+        Message::sign_transaction(
+            ListOwl {
+                owl_id: alice_owls[0],
+                price: 60,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+        Message::sign_transaction(
+            ListOwl {
+                owl_id: alice_owls[1],
+                price: 500,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+        // Listed owl can not be listed twice.
+        Message::sign_transaction(
+            ListOwl {
+                owl_id: alice_owls[0],
+                price: 70,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+        // Listed owl can not be auctioned.
+        Message::sign_transaction(
+            CreateAuction {
+                owl_id: alice_owls[0],
+                start_price: 10,
+                duration: 1_000,
+                cancellable_with_bids: false,
+                buyout_price: 0,
+                kind: AuctionKind::English,
+                reveal_duration: 0,
+                floor_price: 0,
+                decay_interval: 0,
+                reserve_commitment: Hash::zero(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+    ]);
+
+    let statuses: Vec<_> = block
+        .transactions
+        .iter()
+        .map(|tx| tx.status().map_err(|e| e.error_type()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            Ok(()),
+            Ok(()),
+            Err(error_code(ErrorKind::OwlAlreadyListed)),
+            Err(error_code(ErrorKind::OwlAlreadyListed)),
+        ]
+    );
+
+    let block = testkit.create_block_with_transactions(txvec![
+        // Seller can not buy own owl.
+        Message::sign_transaction(
+            BuyOwl { listing_id: 0 },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+        // Price is too high for Bob.
+        Message::sign_transaction(
+            BuyOwl { listing_id: 1 },
+            CRYPTOOWLS_SERVICE_ID,
+            bob_keys.0,
+            &bob_keys.1,
+        ),
+        // Only the seller can delist the owl.
+        Message::sign_transaction(
+            DelistOwl { listing_id: 1 },
+            CRYPTOOWLS_SERVICE_ID,
+            bob_keys.0,
+            &bob_keys.1,
+        ),
+        Message::sign_transaction(
+            BuyOwl { listing_id: 0 },
+            CRYPTOOWLS_SERVICE_ID,
+            bob_keys.0,
+            &bob_keys.1,
+        ),
+        Message::sign_transaction(
+            DelistOwl { listing_id: 1 },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+        // Listing is already closed.
+        Message::sign_transaction(
+            BuyOwl { listing_id: 1 },
+            CRYPTOOWLS_SERVICE_ID,
+            bob_keys.0,
+            &bob_keys.1,
+        ),
+    ]);
+
+    let statuses: Vec<_> = block
+        .transactions
+        .iter()
+        .map(|tx| tx.status().map_err(|e| e.error_type()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            Err(error_code(ErrorKind::NoSelfBuying)),
+            Err(error_code(ErrorKind::InsufficientFunds)),
+            Err(error_code(ErrorKind::AccessViolation)),
+            Ok(()),
+            Ok(()),
+            Err(error_code(ErrorKind::ListingClosed)),
+        ]
+    );
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let sold = schema.listings().get(0).unwrap();
+    let delisted = schema.listings().get(1).unwrap();
+    let alice = schema.users().get(&alice_keys.0).unwrap();
+    let bob = schema.users().get(&bob_keys.0).unwrap();
+
+    assert!(sold.closed);
+    assert_eq!(sold.close_reason, CloseReason::Sold);
+    assert!(delisted.closed);
+    assert_eq!(delisted.close_reason, CloseReason::Cancelled);
+    assert!(schema.owl_listing().get(&alice_owls[0]).is_none());
+    assert!(schema.owl_listing().get(&alice_owls[1]).is_none());
+
+    assert_eq!(
+        schema.owls_state().get(&alice_owls[0]).unwrap().owner,
+        bob_keys.0
+    );
+    assert_eq!(
+        schema.owls_state().get(&alice_owls[1]).unwrap().owner,
+        alice_keys.0
+    );
+    assert_eq!(alice.balance, ISSUE_AMOUNT + 60);
+    assert_eq!(bob.balance, ISSUE_AMOUNT - 60);
+    assert_eq!(total_supply(&testkit), supply);
+}
+
+#[test]
+fn test_auctioned_owl_can_not_be_listed() {
+    let (mut testkit, _) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice"]);
+    let alice_keys = &keys[0];
+    let alice_owl = user_owls(&testkit, &alice_keys.0)[0];
+
+    start_auction(&mut testkit, alice_keys, alice_owl, false);
+
+    let block = testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        ListOwl {
+            owl_id: alice_owl,
+            price: 60,
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        alice_keys.0,
+        &alice_keys.1
+    )]);
+
+    assert_eq!(
+        block.transactions[0].status().map_err(|e| e.error_type()),
+        Err(error_code(ErrorKind::OwlAlreadyAuctioned))
+    );
+}
+
+#[test]
+fn test_auction_reserve_price_not_met() {
+    let (mut testkit, time_machine) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob"]);
+    let (alice_keys, bob_keys) = (&keys[0], &keys[1]);
+    let alice_owl = user_owls(&testkit, &alice_keys.0)[0];
+    let salt = crypto::hash(b"reserve");
+
+    testkit
+        .create_block_with_transactions(txvec![Message::sign_transaction(
+            CreateAuction {
+                owl_id: alice_owl,
+                start_price: 10,
+                duration: 1_000,
+                cancellable_with_bids: false,
+                buyout_price: 0,
+                kind: AuctionKind::English,
+                reveal_duration: 100,
+                floor_price: 0,
+                decay_interval: 0,
+                reserve_commitment: reserve_price_commitment(50, &salt, &alice_keys.0),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1
+        )])
+        .transactions
+        .into_iter()
+        .for_each(|tx| tx.status().unwrap());
+    // Bids below the reserve price are accepted, since nobody knows it yet.
+    make_bid(&mut testkit, bob_keys, 30);
+    assert_eq!(
+        reveal_reserve(&mut testkit, alice_keys, 50, salt),
+        Err(error_code(ErrorKind::WrongAuctionPhase))
+    );
+
+    advance_time(&mut testkit, &time_machine, 1_000);
+    {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        let auction = schema.auctions().get(0).unwrap();
+        assert_eq!(auction.phase, AuctionPhase::Reveal);
+        assert_eq!(auction.auction.reserve_price, 0);
+        assert!(!auction.closed);
+    }
+
+    let block = testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        MakeBid {
+            auction_id: 0,
+            value: 60,
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        bob_keys.0,
+        &bob_keys.1
+    )]);
+    assert_eq!(
+        block.transactions[0].status().map_err(|e| e.error_type()),
+        Err(error_code(ErrorKind::WrongAuctionPhase))
+    );
+    assert_eq!(
+        reveal_reserve(&mut testkit, bob_keys, 50, salt),
+        Err(error_code(ErrorKind::AccessViolation))
+    );
+    assert_eq!(
+        reveal_reserve(&mut testkit, alice_keys, 20, salt),
+        Err(error_code(ErrorKind::CommitmentMismatch))
+    );
+    assert_eq!(reveal_reserve(&mut testkit, alice_keys, 50, salt), Ok(()));
+
+    advance_time(&mut testkit, &time_machine, 100);
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let auction = schema.auctions().get(0).unwrap();
+    let alice = schema.users().get(&alice_keys.0).unwrap();
+    let bob = schema.users().get(&bob_keys.0).unwrap();
+
+    assert!(auction.closed);
+    assert_eq!(auction.auction.reserve_price, 50);
+    assert_eq!(auction.close_reason, CloseReason::ReserveNotMet);
+    assert_eq!(alice.balance, ISSUE_AMOUNT);
+    assert_eq!(bob.balance, ISSUE_AMOUNT);
+    assert_eq!(bob.reserved, 0);
+    assert_eq!(
+        schema.owls_state().get(&alice_owl).unwrap().owner,
+        alice_keys.0
+    );
+    assert!(schema.owl_auction().get(&alice_owl).is_none());
+}
+
+#[test]
+fn test_auction_reserve_price_not_revealed() {
+    let (mut testkit, time_machine) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob"]);
+    let (alice_keys, bob_keys) = (&keys[0], &keys[1]);
+    let alice_owl = user_owls(&testkit, &alice_keys.0)[0];
+    let salt = crypto::hash(b"reserve");
+
+    testkit
+        .create_block_with_transactions(txvec![Message::sign_transaction(
+            CreateAuction {
+                owl_id: alice_owl,
+                start_price: 10,
+                duration: 1_000,
+                cancellable_with_bids: false,
+                buyout_price: 0,
+                kind: AuctionKind::English,
+                reveal_duration: 100,
+                floor_price: 0,
+                decay_interval: 0,
+                reserve_commitment: reserve_price_commitment(50, &salt, &alice_keys.0),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1
+        )])
+        .transactions
+        .into_iter()
+        .for_each(|tx| tx.status().unwrap());
+    make_bid(&mut testkit, bob_keys, 30);
+
+    // The seller can not back out of the sale by keeping the reserve price secret.
+    advance_time(&mut testkit, &time_machine, 1_000);
+    advance_time(&mut testkit, &time_machine, 100);
+    assert_eq!(
+        reveal_reserve(&mut testkit, alice_keys, 50, salt),
+        Err(error_code(ErrorKind::AuctionClosed))
+    );
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let auction = schema.auctions().get(0).unwrap();
+    let alice = schema.users().get(&alice_keys.0).unwrap();
+    let bob = schema.users().get(&bob_keys.0).unwrap();
+
+    assert_eq!(auction.close_reason, CloseReason::Sold);
+    assert_eq!(alice.balance, ISSUE_AMOUNT + 30);
+    assert_eq!(bob.balance, ISSUE_AMOUNT - 30);
+    assert_eq!(
+        schema.owls_state().get(&alice_owl).unwrap().owner,
+        bob_keys.0
+    );
+}
+
+#[test]
+fn test_auction_buyout() {
+    let (mut testkit, _) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob", "Jane"]);
+    let (alice_keys, bob_keys, jane_keys) = (&keys[0], &keys[1], &keys[2]);
+    let alice_owl = user_owls(&testkit, &alice_keys.0)[0];
+    let supply = total_supply(&testkit);
+
+    let block = testkit.create_block_with_transactions(txvec![
+        // Buy-out price below the start price makes no sense.
+        Message::sign_transaction(
+            CreateAuction {
+                owl_id: alice_owl,
+                start_price: 10,
+                duration: 1_000,
+                cancellable_with_bids: false,
+                buyout_price: 5,
+                kind: AuctionKind::English,
+                reveal_duration: 0,
+                floor_price: 0,
+                decay_interval: 0,
+                reserve_commitment: Hash::zero(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+        Message::sign_transaction(
+            CreateAuction {
+                owl_id: alice_owl,
+                start_price: 10,
+                duration: 1_000,
+                cancellable_with_bids: false,
+                buyout_price: 60,
+                kind: AuctionKind::English,
+                reveal_duration: 0,
+                floor_price: 0,
+                decay_interval: 0,
+                reserve_commitment: Hash::zero(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1,
+        ),
+    ]);
+    let statuses: Vec<_> = block
+        .transactions
+        .iter()
+        .map(|tx| tx.status().map_err(|e| e.error_type()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![Err(error_code(ErrorKind::InvalidAuctionPrices)), Ok(())]
+    );
+
+    let block = testkit.create_block_with_transactions(txvec![
+        Message::sign_transaction(
+            MakeBid {
+                auction_id: 0,
+                value: 20,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            bob_keys.0,
+            &bob_keys.1,
+        ),
+        // Bid above the buy-out price settles the auction by the buy-out price.
+        Message::sign_transaction(
+            MakeBid {
+                auction_id: 0,
+                value: 70,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            jane_keys.0,
+            &jane_keys.1,
+        ),
+        Message::sign_transaction(
+            MakeBid {
+                auction_id: 0,
+                value: 80,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            bob_keys.0,
+            &bob_keys.1,
+        ),
+    ]);
+    let statuses: Vec<_> = block
+        .transactions
+        .iter()
+        .map(|tx| tx.status().map_err(|e| e.error_type()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![Ok(()), Ok(()), Err(error_code(ErrorKind::AuctionClosed))]
+    );
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let auction = schema.auctions().get(0).unwrap();
+    let alice = schema.users().get(&alice_keys.0).unwrap();
+    let bob = schema.users().get(&bob_keys.0).unwrap();
+    let jane = schema.users().get(&jane_keys.0).unwrap();
+
+    assert!(auction.closed);
+    assert_eq!(auction.close_reason, CloseReason::Sold);
+    assert_eq!(schema.auction_bids(0).last().unwrap().value, 60);
+    assert_eq!(alice.balance, ISSUE_AMOUNT + 60);
+    assert_eq!(bob.balance, ISSUE_AMOUNT);
+    assert_eq!(bob.reserved, 0);
+    assert_eq!(jane.balance, ISSUE_AMOUNT - 60);
+    assert_eq!(jane.reserved, 0);
+    assert_eq!(
+        schema.owls_state().get(&alice_owl).unwrap().owner,
+        jane_keys.0
+    );
+    assert_eq!(total_supply(&testkit), supply);
+}
+
+fn advance_time(testkit: &mut TestKit, time_machine: &MockTimeProvider, seconds: i64) {
+    time_machine.add_time(Duration::seconds(seconds));
+    // The first block commits the stale time, the second one the updated time.
+    let height = testkit.height();
+    testkit.create_blocks_until(height.next().next());
+}
+
+fn make_bid(testkit: &mut TestKit, bidder: &(PublicKey, SecretKey), value: u64) {
+    testkit
+        .create_block_with_transactions(txvec![Message::sign_transaction(
+            MakeBid {
+                auction_id: 0,
+                value,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            bidder.0,
+            &bidder.1
+        )])
+        .transactions
+        .into_iter()
+        .for_each(|tx| tx.status().unwrap());
+}
+
+#[test]
+fn test_auction_extension_chain() {
+    let (mut testkit, time_machine) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob", "Jane"]);
+    let (alice_keys, bob_keys, jane_keys) = (&keys[0], &keys[1], &keys[2]);
+    let alice_owl = user_owls(&testkit, &alice_keys.0)[0];
+    let window = CryptoOwlsConfig::default().auction_extension_window;
+
+    testkit
+        .create_block_with_transactions(txvec![Message::sign_transaction(
+            CreateAuction {
+                owl_id: alice_owl,
+                start_price: 10,
+                duration: 100,
+                cancellable_with_bids: false,
+                buyout_price: 0,
+                kind: AuctionKind::English,
+                reveal_duration: 0,
+                floor_price: 0,
+                decay_interval: 0,
+                reserve_commitment: Hash::zero(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1
+        )])
+        .transactions
+        .into_iter()
+        .for_each(|tx| tx.status().unwrap());
+
+    let auction_ends_at = |testkit: &TestKit| {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        let auction = schema.auctions().get(0).unwrap();
+        auction.ends_at
+    };
+    let started_at = {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        let auction = schema.auctions().get(0).unwrap();
+        assert_eq!(auction.ends_at, auction.started_at + Duration::seconds(100));
+        auction.started_at
+    };
+
+    // Early bid does not change the end time.
+    make_bid(&mut testkit, bob_keys, 20);
+    assert_eq!(
+        auction_ends_at(&testkit),
+        started_at + Duration::seconds(100)
+    );
+
+    // Bid within the extension window moves the end time.
+    advance_time(&mut testkit, &time_machine, 90);
+    make_bid(&mut testkit, jane_keys, 30);
+    let extended = started_at + Duration::seconds(90 + window);
+    assert_eq!(auction_ends_at(&testkit), extended);
+
+    // The original end time has passed, but the auction is still open.
+    advance_time(&mut testkit, &time_machine, 50);
+    {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        assert!(!schema.auctions().get(0).unwrap().closed);
+    }
+
+    // Another late bid extends the auction once again.
+    make_bid(&mut testkit, bob_keys, 40);
+    let extended_again = started_at + Duration::seconds(140 + window);
+    assert_eq!(auction_ends_at(&testkit), extended_again);
+    {
+        // Only the latest end time is kept in the expiry index.
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        let expiry = schema.auction_expiry().iter().collect::<Vec<_>>();
+        assert_eq!(
+            expiry,
+            vec![AuctionExpiry {
+                ends_at: extended_again,
+                auction_id: 0,
+            }]
+        );
+    }
+
+    advance_time(&mut testkit, &time_machine, window);
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let auction = schema.auctions().get(0).unwrap();
+    let bob = schema.users().get(&bob_keys.0).unwrap();
+    let jane = schema.users().get(&jane_keys.0).unwrap();
+
+    assert!(auction.closed);
+    assert_eq!(auction.close_reason, CloseReason::Sold);
+    assert_eq!(auction.ends_at, extended_again);
+    assert_eq!(schema.auction_expiry().iter().next(), None);
+    assert_eq!(bob.balance, ISSUE_AMOUNT - 40);
+    assert_eq!(jane.balance, ISSUE_AMOUNT);
+    assert_eq!(
+        schema.owls_state().get(&alice_owl).unwrap().owner,
+        bob_keys.0
+    );
+}
+
+#[test]
+fn test_auction_extension_window_config() {
+    let time_machine = MockTimeProvider::default();
+    let config = CryptoOwlsConfig {
+        auction_extension_window: 300,
+        ..CryptoOwlsConfig::default()
+    };
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CryptoOwlsService::new(config))
+        .with_service(TimeService::with_provider(time_machine.clone()))
+        .create();
+    testkit.create_blocks_until(Height(2));
+
+    let keys = create_users(&mut testkit, &["Alice", "Bob"]);
+    let (alice_keys, bob_keys) = (&keys[0], &keys[1]);
+    let alice_owl = user_owls(&testkit, &alice_keys.0)[0];
+    start_auction(&mut testkit, alice_keys, alice_owl, false);
+
+    // The bid is not within the default window, but within the configured one.
+    advance_time(&mut testkit, &time_machine, 800);
+    make_bid(&mut testkit, bob_keys, 20);
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let auction = schema.auctions().get(0).unwrap();
+    assert_eq!(
+        auction.ends_at,
+        auction.started_at + Duration::seconds(800 + 300)
+    );
+}
+
+fn start_sealed_auction(
+    testkit: &mut TestKit,
+    seller: &(PublicKey, SecretKey),
+    owl_id: Hash,
+    kind: AuctionKind,
+    reserve_commitment: Hash,
+) {
+    testkit
+        .create_block_with_transactions(txvec![Message::sign_transaction(
+            CreateAuction {
+                owl_id,
+                start_price: 10,
+                duration: 100,
+                cancellable_with_bids: false,
+                buyout_price: 0,
+                kind,
+                reveal_duration: 100,
+                floor_price: 0,
+                decay_interval: 0,
+                reserve_commitment,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            seller.0,
+            &seller.1
+        )])
+        .transactions
+        .into_iter()
+        .for_each(|tx| tx.status().unwrap());
+}
+
+fn commit_bid(
+    testkit: &mut TestKit,
+    bidder: &(PublicKey, SecretKey),
+    value: u64,
+    deposit: u64,
+) -> Hash {
+    let salt = crypto::hash(bidder.0.as_ref());
+    testkit
+        .create_block_with_transactions(txvec![Message::sign_transaction(
+            CommitBid {
+                auction_id: 0,
+                commitment: sealed_bid_commitment(value, &salt, &bidder.0),
+                deposit,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            bidder.0,
+            &bidder.1
+        )])
+        .transactions
+        .into_iter()
+        .for_each(|tx| tx.status().unwrap());
+    salt
+}
+
+fn reveal_bid(
+    testkit: &mut TestKit,
+    bidder: &(PublicKey, SecretKey),
+    value: u64,
+    salt: Hash,
+) -> Result<(), TransactionErrorType> {
+    let block = testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        RevealBid {
+            auction_id: 0,
+            value,
+            salt,
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        bidder.0,
+        &bidder.1
+    )]);
+    block.transactions[0].status().map_err(|e| e.error_type())
+}
+
+fn reveal_reserve(
+    testkit: &mut TestKit,
+    seller: &(PublicKey, SecretKey),
+    reserve_price: u64,
+    salt: Hash,
+) -> Result<(), TransactionErrorType> {
+    let block = testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        RevealReserve {
+            auction_id: 0,
+            reserve_price,
+            salt,
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        seller.0,
+        &seller.1
+    )]);
+    block.transactions[0].status().map_err(|e| e.error_type())
+}
+
+#[test]
+fn test_sealed_first_price_auction() {
+    let (mut testkit, time_machine) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob", "Jane"]);
+    let (alice_keys, bob_keys, jane_keys) = (&keys[0], &keys[1], &keys[2]);
+    let alice_owl = user_owls(&testkit, &alice_keys.0)[0];
+    let supply = total_supply(&testkit);
+
+    start_sealed_auction(
+        &mut testkit,
+        alice_keys,
+        alice_owl,
+        AuctionKind::SealedFirstPrice,
+        Hash::zero(),
+    );
+    let bob_salt = commit_bid(&mut testkit, bob_keys, 40, 50);
+    let jane_salt = commit_bid(&mut testkit, jane_keys, 30, 90);
+
+    // Open bids are not accepted, and sealed ones can not be revealed yet.
+    let block = testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        MakeBid {
+            auction_id: 0,
+            value: 20,
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        bob_keys.0,
+        &bob_keys.1
+    )]);
+    assert_eq!(
+        block.transactions[0].status().map_err(|e| e.error_type()),
+        Err(error_code(ErrorKind::WrongAuctionKind))
+    );
+    assert_eq!(
+        reveal_bid(&mut testkit, bob_keys, 40, bob_salt),
+        Err(error_code(ErrorKind::WrongAuctionPhase))
+    );
+
+    {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        let bob = schema.users().get(&bob_keys.0).unwrap();
+        assert_eq!(bob.balance, ISSUE_AMOUNT - 50);
+        assert_eq!(bob.reserved, 50);
+        assert!(schema.auction_bids(0).is_empty());
+    }
+
+    advance_time(&mut testkit, &time_machine, 100);
+    {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        let auction = schema.auctions().get(0).unwrap();
+        assert_eq!(auction.phase, AuctionPhase::Reveal);
+        assert!(!auction.closed);
+    }
+
+    assert_eq!(
+        reveal_bid(&mut testkit, bob_keys, 45, bob_salt),
+        Err(error_code(ErrorKind::CommitmentMismatch))
+    );
+    assert_eq!(reveal_bid(&mut testkit, bob_keys, 40, bob_salt), Ok(()));
+    assert_eq!(reveal_bid(&mut testkit, jane_keys, 30, jane_salt), Ok(()));
+
+    advance_time(&mut testkit, &time_machine, 100);
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let auction = schema.auctions().get(0).unwrap();
+    let alice = schema.users().get(&alice_keys.0).unwrap();
+    let bob = schema.users().get(&bob_keys.0).unwrap();
+    let jane = schema.users().get(&jane_keys.0).unwrap();
+
+    assert!(auction.closed);
+    assert_eq!(auction.phase, AuctionPhase::Closed);
+    assert_eq!(auction.close_reason, CloseReason::Sold);
+    assert_eq!(alice.balance, ISSUE_AMOUNT + 40);
+    assert_eq!(bob.balance, ISSUE_AMOUNT - 40);
+    assert_eq!(bob.reserved, 0);
+    assert_eq!(jane.balance, ISSUE_AMOUNT);
+    assert_eq!(jane.reserved, 0);
+    assert_eq!(
+        schema.owls_state().get(&alice_owl).unwrap().owner,
+        bob_keys.0
+    );
+    assert_eq!(total_supply(&testkit), supply);
+}
+
+#[test]
+fn test_sealed_second_price_auction() {
+    let (mut testkit, time_machine) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob", "Jane", "Mike"]);
+    let (alice_keys, bob_keys, jane_keys, mike_keys) = (&keys[0], &keys[1], &keys[2], &keys[3]);
+    let alice_owl = user_owls(&testkit, &alice_keys.0)[0];
+    let supply = total_supply(&testkit);
+
+    let reserve_salt = crypto::hash(b"reserve");
+    start_sealed_auction(
+        &mut testkit,
+        alice_keys,
+        alice_owl,
+        AuctionKind::SealedSecondPrice,
+        reserve_price_commitment(25, &reserve_salt, &alice_keys.0),
+    );
+    let bob_salt = commit_bid(&mut testkit, bob_keys, 60, 60);
+    let jane_salt = commit_bid(&mut testkit, jane_keys, 20, 20);
+    // Mike never reveals the bid.
+    commit_bid(&mut testkit, mike_keys, 90, 90);
+
+    advance_time(&mut testkit, &time_machine, 100);
+    assert_eq!(reveal_bid(&mut testkit, bob_keys, 60, bob_salt), Ok(()));
+    assert_eq!(reveal_bid(&mut testkit, jane_keys, 20, jane_salt), Ok(()));
+    assert_eq!(
+        reveal_reserve(&mut testkit, alice_keys, 25, reserve_salt),
+        Ok(())
+    );
+    advance_time(&mut testkit, &time_machine, 100);
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let auction = schema.auctions().get(0).unwrap();
+    let alice = schema.users().get(&alice_keys.0).unwrap();
+    let bob = schema.users().get(&bob_keys.0).unwrap();
+    let jane = schema.users().get(&jane_keys.0).unwrap();
+    let mike = schema.users().get(&mike_keys.0).unwrap();
+
+    // The winner pays the second highest bid, but not less than the reserve price.
+    assert_eq!(auction.close_reason, CloseReason::Sold);
+    assert_eq!(alice.balance, ISSUE_AMOUNT + 25);
+    assert_eq!(bob.balance, ISSUE_AMOUNT - 25);
+    assert_eq!(bob.reserved, 0);
+    assert_eq!(jane.balance, ISSUE_AMOUNT);
+    assert_eq!(mike.balance, ISSUE_AMOUNT);
+    assert_eq!(mike.reserved, 0);
+    assert_eq!(
+        schema.owls_state().get(&alice_owl).unwrap().owner,
+        bob_keys.0
+    );
+    assert_eq!(total_supply(&testkit), supply);
+}
+
+#[test]
+fn test_dutch_auction() {
+    let (mut testkit, time_machine) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob", "Jane"]);
+    let (alice_keys, bob_keys, jane_keys) = (&keys[0], &keys[1], &keys[2]);
+    let alice_owl = user_owls(&testkit, &alice_keys.0)[0];
+    let supply = total_supply(&testkit);
+
+    let block = testkit.create_block_with_transactions(txvec![
+        // Price can not grow up to the floor price.
+        Message::sign_transaction(
+            CreateAuction {
+                owl_id: alice_owl,
+                start_price: 90,
+                duration: 100,
+                cancellable_with_bids: false,
+                buyout_price: 0,
+                kind: AuctionKind::Dutch,
+                reveal_duration: 0,
+                floor_price: 100,
+                decay_interval: 0,
+                reserve_commitment: Hash::zero(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1
+        ),
+        Message::sign_transaction(
+            CreateAuction {
+                owl_id: alice_owl,
+                start_price: 90,
+                duration: 100,
+                cancellable_with_bids: false,
+                buyout_price: 0,
+                kind: AuctionKind::Dutch,
+                reveal_duration: 0,
+                floor_price: 30,
+                decay_interval: 0,
+                reserve_commitment: Hash::zero(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            alice_keys.0,
+            &alice_keys.1
+        ),
+    ]);
+    assert_eq!(
+        block
+            .transactions
+            .iter()
+            .map(|tx| tx.status().map_err(|e| e.error_type()))
+            .collect::<Vec<_>>(),
+        vec![Err(error_code(ErrorKind::InvalidFloorPrice)), Ok(())]
+    );
+
+    {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        let mut auction = schema.auctions().get(0).unwrap();
+        let started_at = auction.started_at;
+        assert_eq!(auction.dutch_price(started_at), 90);
+        assert_eq!(auction.dutch_price(started_at + Duration::seconds(50)), 60);
+        assert_eq!(auction.dutch_price(started_at + Duration::seconds(500)), 30);
+
+        // Stepwise decay.
+        auction.auction.decay_interval = 30;
+        assert_eq!(auction.dutch_price(started_at + Duration::seconds(50)), 72);
+        assert_eq!(auction.dutch_price(started_at + Duration::seconds(100)), 36);
+    }
+
+    advance_time(&mut testkit, &time_machine, 50);
+
+    // Bid below the current price is rejected, the first bid covering it wins.
+    // Only the current price is charged, so the bid may exceed the balance.
+    let block = testkit.create_block_with_transactions(txvec![
+        Message::sign_transaction(
+            MakeBid {
+                auction_id: 0,
+                value: 50,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            bob_keys.0,
+            &bob_keys.1
+        ),
+        Message::sign_transaction(
+            MakeBid {
+                auction_id: 0,
+                value: ISSUE_AMOUNT + 70,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            jane_keys.0,
+            &jane_keys.1
+        ),
+        Message::sign_transaction(
+            MakeBid {
+                auction_id: 0,
+                value: 80,
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            bob_keys.0,
+            &bob_keys.1
+        ),
+    ]);
+    assert_eq!(
+        block
+            .transactions
+            .iter()
+            .map(|tx| tx.status().map_err(|e| e.error_type()))
+            .collect::<Vec<_>>(),
+        vec![
+            Err(error_code(ErrorKind::BidTooLow)),
+            Ok(()),
+            Err(error_code(ErrorKind::AuctionClosed)),
+        ]
+    );
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let auction = schema.auctions().get(0).unwrap();
+    let alice = schema.users().get(&alice_keys.0).unwrap();
+    let jane = schema.users().get(&jane_keys.0).unwrap();
+
+    assert!(auction.closed);
+    assert_eq!(auction.close_reason, CloseReason::Sold);
+    assert_eq!(alice.balance, ISSUE_AMOUNT + 60);
+    assert_eq!(jane.balance, ISSUE_AMOUNT - 60);
+    assert_eq!(jane.reserved, 0);
+    assert_eq!(
+        schema.owls_state().get(&alice_owl).unwrap().owner,
+        jane_keys.0
+    );
+    assert_eq!(total_supply(&testkit), supply);
+}
+
+#[test]
+fn test_config_change() {
+    let (mut testkit, _) = init_testkit();
+    {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        assert_eq!(schema.config(), CryptoOwlsConfig::default());
+    }
+
+    let new_config = CryptoOwlsConfig {
+        issue_amount: 500,
+        ..CryptoOwlsConfig::default()
+    };
+    let actual_from = testkit.height().next().next();
+    let mut proposal = testkit.configuration_change_proposal();
+    proposal.set_service_config(CRYPTOOWLS_SERVICE_NAME, new_config.clone());
+    proposal.set_actual_from(actual_from);
+    testkit.commit_configuration_change(proposal);
+    testkit.create_blocks_until(actual_from);
+
+    let keys = create_users(&mut testkit, &["Alice"]);
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert_eq!(schema.config(), new_config);
+    assert_eq!(schema.users().get(&keys[0].0).unwrap().balance, 500);
+}
+
+#[test]
+fn test_partial_config() {
+    // The configuration written before the inbreeding parameters were introduced.
+    let config: CryptoOwlsConfig = serde_json::from_value(json!({
+        "issue_amount": 500,
+        "issue_timeout": 10,
+        "breeding_timeout": 20,
+        "breeding_price": 30,
+        "hatching_timeout": 40
+    }))
+    .unwrap();
+    assert_eq!(
+        config,
+        CryptoOwlsConfig {
+            issue_amount: 500,
+            issue_timeout: 10,
+            breeding_timeout: 20,
+            breeding_price: 30,
+            hatching_timeout: 40,
+            ..CryptoOwlsConfig::default()
+        }
+    );
+}
+
+#[test]
+fn test_invalid_config_change() {
+    let (mut testkit, _) = init_testkit();
+    let valid_config = CryptoOwlsConfig {
+        issue_amount: 500,
+        breeding_price: 7,
+        ..CryptoOwlsConfig::default()
+    };
+    let actual_from = testkit.height().next().next();
+    let mut proposal = testkit.configuration_change_proposal();
+    proposal.set_service_config(CRYPTOOWLS_SERVICE_NAME, valid_config.clone());
+    proposal.set_actual_from(actual_from);
+    testkit.commit_configuration_change(proposal);
+    testkit.create_blocks_until(actual_from);
+
+    let invalid_config = CryptoOwlsConfig {
+        issue_amount: 900,
+        breeding_timeout: -1,
+        ..CryptoOwlsConfig::default()
+    };
+    assert!(invalid_config.validate().is_err());
+    let invalid_sections = vec![
+        serde_json::to_value(invalid_config).unwrap(),
+        json!("not a configuration"),
+        json!({ "breeding_price": "100" }),
+    ];
+
+    // The configuration service does not check the service section, so the parameters
+    // of the last valid configuration stay in effect.
+    let names = ["Alice", "Bob", "Carol"];
+    for (section, name) in invalid_sections.into_iter().zip(&names) {
+        let actual_from = testkit.height().next().next();
+        let mut proposal = testkit.configuration_change_proposal();
+        proposal.set_service_config(CRYPTOOWLS_SERVICE_NAME, section);
+        proposal.set_actual_from(actual_from);
+        testkit.commit_configuration_change(proposal);
+        testkit.create_blocks_until(actual_from);
+
+        let keys = create_users(&mut testkit, &[*name]);
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        assert_eq!(schema.config(), valid_config);
+        assert_eq!(schema.users().get(&keys[0].0).unwrap().balance, 500);
+    }
+}
+
+#[test]
+fn test_trait_stats() {
+    let (mut testkit, time_machine) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob", "Carol"]);
+    let (alice_pk, alice_sk) = keys[0].clone();
+    let parents = user_owls(&testkit, &alice_pk);
+
+    time_machine.add_time(Duration::seconds(200));
+    testkit.create_blocks_until(Height(8));
+    testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        MakeOwl {
+            name: "Abel".to_owned(),
+            father_id: parents[0],
+            mother_id: parents[1],
+            seed: Utc::now(),
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        alice_pk,
+        &alice_sk
+    )]);
+    hatch_eggs(&mut testkit, &time_machine);
+
+    // Transferred owls are not counted again.
+    testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        TransferOwl {
+            owl_id: parents[0],
+            to: keys[1].0,
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        alice_pk,
+        &alice_sk
+    )]);
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert_eq!(schema.user_owls(&alice_pk).iter().count(), 2);
+    let stats = schema
+        .trait_stats()
+        .get()
+        .expect("No trait statistics persisted");
+    assert_eq!(stats.owls, 7);
+
+    let mut expected = TraitStats::new();
+    for owl_state in schema.owls_state().values() {
+        expected.add(&Traits::from(&owl_state.owl));
+    }
+    assert_eq!(stats, expected);
+    for counts in &[&stats.eyes, &stats.wings, &stats.chest, &stats.tail] {
+        assert_eq!(counts.iter().sum::<u64>(), 7);
+    }
+}
+
+#[test]
+fn test_unique_owl_names() {
+    let (mut testkit, time_machine) = init_testkit();
+    let (pk, sk) = create_users(&mut testkit, &["Alice"]).remove(0);
+    let (other_pk, _) = create_users(&mut testkit, &["ALICE"]).remove(0);
+
+    // Starter owls of the namesake are disambiguated.
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let mut names: Vec<_> = user_owls(&testkit, &other_pk)
+        .iter()
+        .map(|id| schema.owls_state().get(id).unwrap().owl.name)
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["ALICE's Adam 2", "ALICE's Eve 2"]);
+    let parents = user_owls(&testkit, &pk);
+
+    time_machine.add_time(Duration::seconds(200));
+    let height = testkit.height();
+    testkit.create_blocks_until(height.next().next());
+
+    let breed = |name: &str| {
+        Message::sign_transaction(
+            MakeOwl {
+                name: name.to_owned(),
+                father_id: parents[0],
+                mother_id: parents[1],
+                seed: Utc::now(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            pk,
+            &sk,
+        )
+    };
+    let egg = breed("Abel");
+    let egg_id = egg.hash();
+    let block =
+        testkit.create_block_with_transactions(txvec![breed(" alice's  EVE "), breed(" \t "), egg]);
+    assert_eq!(
+        block.transactions[0].status().map_err(|e| e.error_type()),
+        Err(error_code(ErrorKind::OwlNameTaken))
+    );
+    assert_eq!(
+        block.transactions[1].status().map_err(|e| e.error_type()),
+        Err(error_code(ErrorKind::EmptyName))
+    );
+    block.transactions[2].status().unwrap();
+
+    // The name of the egg is reserved for the hatched owl.
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert_eq!(schema.owl_names().get(&owl_name_key("ABEL")), Some(egg_id));
+    assert!(!schema.is_owl_name_free("abel"));
+
+    hatch_eggs(&mut testkit, &time_machine);
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let owl_id = schema.owl_names().get(&owl_name_key("Abel")).unwrap();
+    assert_eq!(schema.owls_state().get(&owl_id).unwrap().owl.name, "Abel");
+}
+
+fn rename_owl(
+    testkit: &mut TestKit,
+    owner: &(PublicKey, SecretKey),
+    owl_id: Hash,
+    name: &str,
+) -> Result<(), TransactionErrorType> {
+    let block = testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        RenameOwl {
+            owl_id,
+            name: name.to_owned(),
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        owner.0,
+        &owner.1
+    )]);
+    block.transactions[0].status().map_err(|e| e.error_type())
+}
+
+#[test]
+fn test_rename_owl() {
+    let (mut testkit, _) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob"]);
+    let owls = user_owls(&testkit, &keys[0].0);
+    let owl_id = owls[0];
+    let old_name = {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        schema.owls_state().get(&owl_id).unwrap().name
+    };
+
+    assert_eq!(rename_owl(&mut testkit, &keys[0], owl_id, "Hedwig"), Ok(()));
+
+    // The owl keeps its identifier, the old name is released.
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let owl_state = schema.owls_state().get(&owl_id).unwrap();
+    assert_eq!(owl_state.name, "Hedwig");
+    assert_eq!(owl_state.owl.name, old_name);
+    assert_eq!(owl_state.owl.hash(), owl_id);
+    assert_eq!(
+        schema.owl_names().get(&owl_name_key("hedwig")),
+        Some(owl_id)
+    );
+    assert!(schema.is_owl_name_free(&old_name));
+    assert_eq!(user_owls(&testkit, &keys[0].0), owls);
+
+    // The owl may change the case of its own name, but not take the name of another owl.
+    assert_eq!(rename_owl(&mut testkit, &keys[0], owl_id, "HEDWIG"), Ok(()));
+    let other_name = {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        schema.owls_state().get(&owls[1]).unwrap().name
+    };
+    assert_eq!(
+        rename_owl(&mut testkit, &keys[0], owl_id, &other_name),
+        Err(error_code(ErrorKind::OwlNameTaken))
+    );
+    for name in &["", "  "] {
+        assert_eq!(
+            rename_owl(&mut testkit, &keys[0], owl_id, name),
+            Err(error_code(ErrorKind::EmptyName))
+        );
+    }
+
+    // Only the owner can rename the owl.
+    assert_eq!(
+        rename_owl(&mut testkit, &keys[1], owl_id, "Errol"),
+        Err(error_code(ErrorKind::AccessViolation))
+    );
+    assert_eq!(
+        rename_owl(&mut testkit, &keys[0], crypto::hash(b"owl"), "Errol"),
+        Err(error_code(ErrorKind::OwlNotFound))
+    );
+
+    // The name stays with the owl after the transfer.
+    testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        TransferOwl {
+            owl_id,
+            to: keys[1].0,
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        keys[0].0,
+        &keys[0].1
+    )]);
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert_eq!(schema.owls_state().get(&owl_id).unwrap().name, "HEDWIG");
+}
+
+#[test]
+fn test_update_profile() {
+    let (mut testkit, _) = init_testkit();
+    let (pk, sk) = create_users(&mut testkit, &["Alice"]).remove(0);
+    let balance = {
+        let snapshot = testkit.snapshot();
+        CryptoOwlsSchema::new(&snapshot)
+            .users()
+            .get(&pk)
+            .unwrap()
+            .balance
+    };
+
+    let update = |name: &str| {
+        Message::sign_transaction(
+            UpdateProfile {
+                name: name.to_owned(),
+            },
+            CRYPTOOWLS_SERVICE_ID,
+            pk,
+            &sk,
+        )
+    };
+    let block =
+        testkit.create_block_with_transactions(txvec![update("Alice Liddell"), update(" ")]);
+    assert_eq!(
+        block.transactions[1].status().map_err(|e| e.error_type()),
+        Err(error_code(ErrorKind::EmptyName))
+    );
+
+    let snapshot = testkit.snapshot();
+    let user = CryptoOwlsSchema::new(&snapshot).users().get(&pk).unwrap();
+    assert_eq!(user.name, "Alice Liddell");
+    assert_eq!(user.balance, balance);
+
+    // New users are named as well.
+    let (pk, sk) = crypto::gen_keypair();
+    let block = testkit.create_block_with_transactions(txvec![Message::sign_transaction(
+        CreateUser {
+            name: String::new(),
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        pk,
+        &sk
+    )]);
+    assert_eq!(
+        block.transactions[0].status().map_err(|e| e.error_type()),
+        Err(error_code(ErrorKind::EmptyName))
+    );
+}
+
+#[test]
+fn test_schema_migration() {
+    let (mut testkit, _) = init_testkit();
+    create_users(&mut testkit, &["Alice", "Bob"]);
+
+    // Simulate the data stored before the owl names were kept in `CryptoOwlState`:
+    // no current names, and one of the names is not registered and taken by a newer owl.
+    let taken_id = crypto::hash(b"newer owl");
+    let mut fork = testkit.blockchain_mut().fork();
+    {
+        let mut schema = CryptoOwlsSchema::new(&mut fork);
+        let owls: Vec<_> = schema.owls_state().iter().collect();
+        for (owl_id, mut owl_state) in owls {
+            if owl_state.owl.name == "Bob's Eve" {
+                schema
+                    .owl_names_mut()
+                    .put(&owl_name_key(&owl_state.owl.name), taken_id);
+            }
+            owl_state.name = String::new();
+            schema.owls_state_mut().put(&owl_id, owl_state);
+        }
+        schema.schema_version_mut().set(0);
+    }
+    testkit.blockchain_mut().merge(fork.into_patch()).unwrap();
+
+    testkit.create_block();
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert_eq!(schema.schema_version().get(), Some(SCHEMA_VERSION));
+    for (owl_id, owl_state) in schema.owls_state().iter() {
+        let expected = if owl_state.owl.name == "Bob's Eve" {
+            "Bob's Eve 2".to_owned()
+        } else {
+            owl_state.owl.name.clone()
+        };
+        assert_eq!(owl_state.name, expected);
+        assert_eq!(
+            schema.owl_names().get(&owl_name_key(&expected)),
+            Some(owl_id)
+        );
+    }
+    assert_eq!(
+        schema.owl_names().get(&owl_name_key("Bob's Eve")),
+        Some(taken_id)
+    );
+}
+
+#[test]
+fn test_eggs_migration() {
+    let mock_provider = MockTimeProvider::default();
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(CryptoOwlsService::default().with_randomness(ManualRandomness::default()))
+        .with_service(TimeService::with_provider(mock_provider.clone()))
+        .create();
+    testkit.create_blocks_until(Height(2));
+    let (pk, _) = create_users(&mut testkit, &["Alice"]).remove(0);
+
+    // Simulate the eggs laid before the egg indexes: the incubated one
+    // and the one waiting for the randomness.
+    let incubated_id = crypto::hash(b"incubated egg");
+    let hatching_id = crypto::hash(b"hatching egg");
+    let mut fork = testkit.blockchain_mut().fork();
+    let hatch_at = current_time(&fork).unwrap() + Duration::days(1);
+    {
+        let mut schema = CryptoOwlsSchema::new(&mut fork);
+        for (egg_id, hatching_height) in &[(incubated_id, 0), (hatching_id, 3)] {
+            let egg = Egg {
+                name: "Abel".to_owned(),
+                father_dna: 0,
+                mother_dna: 0,
+                owner: pk,
+                laid_at: 2,
+                bred_at: hatch_at,
+                hatch_at,
+                hatching_height: *hatching_height,
+                father_id: Hash::zero(),
+                mother_id: Hash::zero(),
+                inbreeding: 0,
+            };
+            schema.eggs_mut().put(egg_id, egg);
+        }
+        schema.schema_version_mut().set(2);
+    }
+    testkit.blockchain_mut().merge(fork.into_patch()).unwrap();
+
+    testkit.create_block();
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert_eq!(schema.schema_version().get(), Some(SCHEMA_VERSION));
+    assert_eq!(
+        schema.incubated_eggs().iter().collect::<Vec<_>>(),
+        vec![EggIncubation {
+            hatch_at,
+            egg_id: incubated_id,
+        }]
+    );
+    assert_eq!(
+        schema.hatching_eggs().iter().collect::<Vec<_>>(),
+        vec![EggHatching {
+            hatching_height: 3,
+            egg_id: hatching_id,
+        }]
+    );
+}
+
+#[test]
+fn test_history_roots_migration() {
+    let (mut testkit, _) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob"]);
+
+    // Simulate the histories recorded before their roots were stored.
+    let mut fork = testkit.blockchain_mut().fork();
+    {
+        let mut schema = CryptoOwlsSchema::new(&mut fork);
+        schema.user_history_roots_mut().clear();
+        schema.schema_version_mut().set(3);
+    }
+    testkit.blockchain_mut().merge(fork.into_patch()).unwrap();
+
+    testkit.create_block();
+
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    assert_eq!(schema.schema_version().get(), Some(SCHEMA_VERSION));
+    for (pk, _) in &keys {
+        assert_eq!(
+            schema.user_history_roots().get(pk),
+            Some(schema.user_history(pk).merkle_root())
+        );
+    }
+}
+
+/// Encodes the auction state the way it was stored before the auction end time was stored.
+/// `exonum.Hash` and `exonum.PublicKey` are messages with a single `bytes data = 1` field.
+fn legacy_auction_bytes(auction_state: &AuctionState, closed: bool) -> Vec<u8> {
+    fn message<F>(write: F) -> Vec<u8>
+    where
+        F: FnOnce(&mut CodedOutputStream) -> ProtobufResult<()>,
+    {
+        let mut bytes = Vec::new();
+        {
+            let mut out = CodedOutputStream::vec(&mut bytes);
+            write(&mut out).unwrap();
+            out.flush().unwrap();
+        }
+        bytes
+    }
+
+    let auction = &auction_state.auction;
+    let auction_bytes = message(|out| {
+        out.write_bytes(
+            1,
+            &message(|out| out.write_bytes(1, auction.public_key.as_ref())),
+        )?;
+        out.write_bytes(
+            2,
+            &message(|out| out.write_bytes(1, auction.owl_id.as_ref())),
+        )?;
+        out.write_uint64(3, auction.start_price)?;
+        out.write_uint64(4, auction.duration)
+    });
+    let started_at = message(|out| {
+        out.write_int64(1, auction_state.started_at.timestamp())?;
+        out.write_int32(2, auction_state.started_at.timestamp_subsec_nanos() as i32)
+    });
+    let bidding_merkle_root =
+        message(|out| out.write_bytes(1, auction_state.bidding_merkle_root.as_ref()));
+    message(|out| {
+        out.write_uint64(1, auction_state.id)?;
+        out.write_bytes(2, &auction_bytes)?;
+        out.write_bytes(3, &started_at)?;
+        out.write_bytes(4, &bidding_merkle_root)?;
+        out.write_bool(5, closed)
+    })
+}
+
+#[test]
+fn test_legacy_auctions_migration() {
+    let (mut testkit, time_machine) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob", "Jane"]);
+    let (alice_keys, bob_keys, jane_keys) = (&keys[0], &keys[1], &keys[2]);
+    let alice_owl = user_owls(&testkit, &alice_keys.0)[0];
+    let jane_owl = user_owls(&testkit, &jane_keys.0)[0];
+
+    start_auction(&mut testkit, alice_keys, alice_owl, false);
+    make_bid(&mut testkit, bob_keys, 20);
+    start_auction(&mut testkit, jane_keys, jane_owl, false);
+
+    // Simulate the auctions stored before the expiry index: the open one with a bid
+    // and the closed one without bids.
+    let mut fork = testkit.blockchain_mut().fork();
+    {
+        let mut schema = CryptoOwlsSchema::new(&mut fork);
+        for auction_id in 0..2 {
+            let auction_state = schema.auctions().get(auction_id).unwrap();
+            let closed = auction_id == 1;
+            if closed {
+                schema
+                    .owl_auction_mut()
+                    .remove(&auction_state.auction.owl_id);
+            }
+            schema.auction_expiry_mut().remove(&AuctionExpiry {
+                ends_at: auction_state.ends_at,
+                auction_id,
+            });
+            let bytes = legacy_auction_bytes(&auction_state, closed);
+            ProofListIndex::new("cryptoowls.auctions", &mut *schema.view).set(auction_id, bytes);
+        }
+        schema.schema_version_mut().set(1);
+    }
+    testkit.blockchain_mut().merge(fork.into_patch()).unwrap();
+
+    // The data is migrated before the first transaction after the upgrade is executed.
+    make_bid(&mut testkit, jane_keys, 30);
+    {
+        let snapshot = testkit.snapshot();
+        let schema = CryptoOwlsSchema::new(&snapshot);
+        assert_eq!(schema.schema_version().get(), Some(SCHEMA_VERSION));
+
+        let open = schema.auctions().get(0).unwrap();
+        assert_eq!(open.ends_at, open.started_at + Duration::seconds(1_000));
+        assert_eq!(open.phase, AuctionPhase::Bidding);
+        assert_eq!(open.auction.reserve_commitment, Hash::zero());
+        assert_eq!(open.commitments_merkle_root, Hash::zero());
+
+        let closed = schema.auctions().get(1).unwrap();
+        assert_eq!(closed.phase, AuctionPhase::Closed);
+        assert_eq!(closed.close_reason, CloseReason::Expired);
+
+        assert_eq!(
+            schema.auction_expiry().iter().collect::<Vec<_>>(),
+            vec![AuctionExpiry {
+                ends_at: open.ends_at,
+                auction_id: 0,
+            }]
+        );
+    }
+
+    // The open auction is closed in time.
+    advance_time(&mut testkit, &time_machine, 1_000);
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let auction = schema.auctions().get(0).unwrap();
+    assert!(auction.closed);
+    assert_eq!(auction.close_reason, CloseReason::Sold);
+    assert_eq!(
+        schema.owls_state().get(&alice_owl).unwrap().owner,
+        jane_keys.0
+    );
+}
+
+fn user_history(testkit: &TestKit, pk: &PublicKey) -> Vec<UserEvent> {
+    let snapshot = testkit.snapshot();
+    let schema = CryptoOwlsSchema::new(&snapshot);
+    let events: Vec<_> = schema.user_history(pk).iter().collect();
+    events
+}
+
+fn event_kinds(events: &[UserEvent]) -> Vec<EventKind> {
+    events.iter().map(|event| event.kind).collect()
+}
+
+#[test]
+fn test_user_history() {
+    let (mut testkit, time_machine) = init_testkit();
+    let keys = create_users(&mut testkit, &["Alice", "Bob", "Jane"]);
+    let (alice_keys, bob_keys, jane_keys) = (&keys[0], &keys[1], &keys[2]);
+    let alice_owl = user_owls(&testkit, &alice_keys.0)[0];
+
+    // The starter pack.
+    let history = user_history(&testkit, &alice_keys.0);
+    assert_eq!(
+        event_kinds(&history),
+        vec![
+            EventKind::FundsIssued,
+            EventKind::OwlBorn,
+            EventKind::OwlBorn
+        ]
+    );
+    assert_eq!(history[0].amount, ISSUE_AMOUNT);
+    assert_eq!(history[0].height, testkit.height().0);
+    let mut born: Vec<_> = history[1..].iter().map(|event| event.owl_id).collect();
+    born.sort();
+    let mut owls = user_owls(&testkit, &alice_keys.0);
+    owls.sort();
+    assert_eq!(born, owls);
+
+    let transfer = Message::sign_transaction(
+        Transfer {
+            to: bob_keys.0,
+            amount: 5,
+        },
+        CRYPTOOWLS_SERVICE_ID,
+        alice_keys.0,
+        &alice_keys.1,
+    );
+    let transfer_hash = transfer.hash();
+    testkit.create_block_with_transactions(txvec![transfer]);
+    let sent = user_history(&testkit, &alice_keys.0).pop().unwrap();
+    let received = user_history(&testkit, &bob_keys.0).pop().unwrap();
+    assert_eq!(
+        (sent.kind, sent.tx_hash, sent.counterparty, sent.amount),
+        (EventKind::FundsSent, transfer_hash, bob_keys.0, 5)
+    );
+    assert_eq!(
+        (received.kind, received.counterparty, received.amount),
+        (EventKind::FundsReceived, alice_keys.0, 5)
+    );
+
+    // Auction is closed when the block is committed.
+    start_auction(&mut testkit, alice_keys, alice_owl, false);
+    make_bid(&mut testkit, bob_keys, 20);
+    make_bid(&mut testkit, jane_keys, 30);
+    advance_time(&mut testkit, &time_machine, 1_000);
+    testkit.create_block();
+
+    let alice_history = user_history(&testkit, &alice_keys.0);
+    assert_eq!(
+        event_kinds(&alice_history[4..]),
+        vec![
+            EventKind::AuctionCreated,
+            EventKind::OwlSold,
+            EventKind::AuctionClosed
+        ]
+    );
+    let bob_history = user_history(&testkit, &bob_keys.0);
+    assert_eq!(
+        event_kinds(&bob_history[4..]),
+        vec![EventKind::BidPlaced, EventKind::Outbid]
+    );
+    let outbid = &bob_history[5];
+    assert_eq!(
+        (outbid.auction_id, outbid.counterparty, outbid.amount),
+        (0, jane_keys.0, 30)
+    );
+    let jane_history = user_history(&testkit, &jane_keys.0);
+    assert_eq!(
+        event_kinds(&jane_history[3..]),
+        vec![
+            EventKind::BidPlaced,
+            EventKind::OwlBought,
+            EventKind::AuctionClosed
+        ]
+    );
+    let (bought, closed) = (&jane_history[4], &jane_history[5]);
+    assert_eq!(
+        (bought.owl_id, bought.counterparty, bought.amount),
+        (alice_owl, alice_keys.0, 30)
+    );
+    assert_eq!(bought.tx_hash, Hash::zero());
+    assert_eq!(closed.close_reason, CloseReason::Sold);
+    assert_eq!(alice_history[5].counterparty, jane_keys.0);
+}